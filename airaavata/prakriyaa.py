@@ -1,5 +1,6 @@
 import logging
 import os
+import warnings
 from copy import copy
 
 import indic_transliteration
@@ -37,7 +38,57 @@ code_to_sutra = {(s.source, s.code): s.text for s in prakriya_data.load_sutras()
 kosha = Kosha(os.path.join(DATA_PATH, "kosha"))
 
 
+_core_helper = None
+
+
+def get_core_helper():
+  """The Rust PrakriyaHelper (see src/python.rs), or None if the _core extension isn't built."""
+  global _core_helper
+  if _core_helper is None:
+    try:
+      from airaavata import _core
+    except ImportError:
+      warnings.warn("airaavata._core isn't built; falling back to the deprecated Python derivation.",
+                    DeprecationWarning)
+      return None
+    _core_helper = _core.PrakriyaHelper(DATA_PATH)
+  return _core_helper
+
+
+def by_form(prakriyaas):
+  """A dict of form to markdown from (form, markdown) pairs, joining the prakriyAs of a form."""
+  forms = {}
+  for result, prakriya_str in prakriyaas:
+    forms.setdefault(result, []).append(prakriya_str)
+  return {result: "\n".join(strs) for result, strs in forms.items()}
+
+
+def dump_prakriyaas(prakriyaas, out_file_path):
+  """prakriyaas is a dict of form to markdown."""
+  for result, prakriya_str in prakriyaas.items():
+    file_path = os.path.join(out_file_path, file_helper.get_storage_name(text=result) + ".md")
+    os.makedirs(os.path.dirname(file_path), exist_ok=True)
+    md_file = MdFile(file_path)
+    md_file.dump_to_file(metadata={"title": result}, content=prakriya_str, dry_run=False)
+
+
 def lookup_and_derive(shabda, type=None, out_file_path=None):
+  """Derives shabda, a form in any script or a vidyut entry or pada, returning a dict of derived
+  form to markdown.
+
+  Forms are derived by the Rust core. Entries, padas and forms filtered by type still go through
+  get_prakriyaa_str, which the Rust core will replace once it takes them.
+  """
+  core_helper = get_core_helper() if isinstance(shabda, str) and type is None else None
+  if core_helper is not None:
+    prakriyaas = by_form(core_helper.derive(shabda))
+    if len(prakriyaas) == 0:
+      logging.error(f"Can't get entry for {shabda}.")
+      return
+    if out_file_path is not None:
+      dump_prakriyaas(prakriyaas, out_file_path)
+    return prakriyaas
+
   if isinstance(shabda, str):
     entries = kosha.get(slp(shabda))
   else:
@@ -50,16 +101,13 @@ def lookup_and_derive(shabda, type=None, out_file_path=None):
   for entry in entries:
     prakriyas = v.derive(entry)
     prakriyaas = get_prakriyaa_str(prakriyas)
-    for result, prakriya_str in prakriyaas.items(): 
-      if out_file_path is not None:
-        file_path = os.path.join(out_file_path, file_helper.get_storage_name(text=result) + ".md")
-        os.makedirs(os.path.dirname(file_path), exist_ok=True)
-        md_file = MdFile(file_path)
-        md_file.dump_to_file(metadata={"title": result}, content=prakriya_str, dry_run=False)
+    if out_file_path is not None:
+      dump_prakriyaas(prakriyaas, out_file_path)
   return prakriyaas
 
 
 def get_prakriyaa_str(prakriyas):
+  """Deprecated: renders prakriyAs in Python, as PrakriyaHelper.derive does in Rust."""
   prakriyaas = {}
   for p in prakriyas:
     steps = []
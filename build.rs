@@ -0,0 +1,19 @@
+//! Passes the version of vidyut-prakriya in `Cargo.lock` to the crate as `VIDYUT_PRAKRIYA_VERSION`,
+//! so that dictionary manifests can tell which vidyut built them.
+use std::fs;
+
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+    let version = lock
+        .split("[[package]]")
+        .find(|package| package.contains("name = \"vidyut-prakriya\""))
+        .and_then(|package| {
+            package
+                .lines()
+                .find_map(|line| line.strip_prefix("version = "))
+                .map(|x| x.trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=VIDYUT_PRAKRIYA_VERSION={}", version);
+}
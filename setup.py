@@ -10,6 +10,7 @@ from os import path
 
 # Always prefer setuptools over distutils
 from setuptools import setup, find_packages
+from setuptools_rust import Binding, RustExtension
 
 here = path.abspath(path.dirname(__file__))
 
@@ -116,6 +117,13 @@ setup(
 
   include_package_data = True,
 
+  # The Rust core (see src/python.rs), importable as airaavata._core. setuptools-rust builds the
+  # library as a cdylib itself, so plain cargo builds don't.
+  rust_extensions=[
+    RustExtension("airaavata._core", binding=Binding.PyO3, features=["python"], optional=True),
+  ],
+  zip_safe=False,
+
   # If there are data files included in your packages that need to be
   # installed, specify them here.  If using Python 2.6 or less, then these
   # have to be included in MANIFEST.in as well.
@@ -0,0 +1,318 @@
+//! Grammatical analyses of a surface form, as recorded in the kosha.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- lookup गच्छन्ति --derive --json
+//!     cargo run --release -- batch-lookup words.txt --format tsv --output report.tsv
+use clap::ValueEnum;
+use log::info;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use vidyut_kosha::entries::{PadaEntry, PratipadikaEntry};
+use vidyut_lipi::Scheme;
+use vidyut_prakriya::args::Dhatu;
+
+use crate::dhaatu::gana_name;
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::steps::StepOptions;
+use crate::util::{parse_scheme, to_scheme};
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Inflected form, in any script.
+    pub(crate) form: String,
+
+    /// Script of the form, such as `Slp1` or `HarvardKyoto`. (Default: detected, which can
+    /// misread ASCII text)
+    #[arg(long, value_parser = parse_scheme)]
+    pub(crate) input_scheme: Option<Scheme>,
+
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    /// Also show the prakriyA of each analysis.
+    #[arg(long)]
+    pub(crate) derive: bool,
+
+    #[command(flatten)]
+    pub(crate) steps: StepOptions,
+
+    /// Print JSON instead of one line per analysis.
+    #[arg(long)]
+    pub(crate) json: bool,
+
+    /// Script for forms and lemmas. (Default: `Devanagari`)
+    #[arg(long)]
+    pub(crate) output_scheme: Option<String>,
+}
+
+/// One analysis of a form. Forms and lemmas are in the requested output script; grammatical
+/// categories use vidyut's names (`Lat`, `Prathama`, ...).
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct Analysis {
+    pub(crate) form: String,
+    /// `subanta`, `avyaya`, `tinanta` or `unknown`.
+    pub(crate) kind: &'static str,
+    /// The prAtipadika of a subanta, or the dhAtu of a tinanta or kRdanta.
+    pub(crate) lemma: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) gana: Option<String>,
+    /// vidyut's name for the gaNa (`Bhvadi`), for formats that need ASCII values.
+    #[serde(skip)]
+    pub(crate) gana_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) sanadi: Vec<String>,
+    /// The kRt of a kRdanta. There's no taddhita counterpart, since the kosha keeps taddhitAntas
+    /// as basic prAtipadikas (`PratipadikaEntry` is either `Basic` or `Krdanta`) and so can't say
+    /// which taddhita made one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) krt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) prayoga: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) lakara: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pada: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) purusha: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) linga: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) vibhakti: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) vacana: Option<String>,
+    /// Rendered prakriyA steps, if a derivation was asked for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) derivation: Vec<String>,
+}
+
+impl Analysis {
+    /// `form` is in SLP1.
+    pub(crate) fn new(form: &str, entry: &PadaEntry, scheme: Scheme) -> Self {
+        let mut analysis = Self {
+            form: to_scheme(form, scheme),
+            kind: "unknown",
+            ..Default::default()
+        };
+        match entry {
+            PadaEntry::Subanta(s) => {
+                analysis.kind = "subanta";
+                analysis.linga = Some(format!("{:?}", s.linga()));
+                analysis.vibhakti = Some(format!("{:?}", s.vibhakti()));
+                analysis.vacana = Some(format!("{:?}", s.vacana()));
+                analysis.set_pratipadika(s.pratipadika_entry(), scheme);
+            }
+            // The li~Nga, vibhakti and vacana of an avyaya say nothing, so only its lemma is kept.
+            PadaEntry::Avyaya(a) => {
+                analysis.kind = "avyaya";
+                analysis.set_pratipadika(a.pratipadika_entry(), scheme);
+            }
+            PadaEntry::Tinanta(t) => {
+                analysis.kind = "tinanta";
+                analysis.set_dhatu(t.dhatu_entry().dhatu(), scheme);
+                analysis.prayoga = Some(format!("{:?}", t.prayoga()));
+                analysis.lakara = Some(format!("{:?}", t.lakara()));
+                analysis.pada = Some(format!("{:?}", t.pada()));
+                analysis.purusha = Some(format!("{:?}", t.purusha()));
+                analysis.vacana = Some(format!("{:?}", t.vacana()));
+            }
+            PadaEntry::Unknown => (),
+        }
+        analysis
+    }
+
+    fn set_pratipadika(&mut self, entry: &PratipadikaEntry, scheme: Scheme) {
+        match entry {
+            PratipadikaEntry::Basic(basic) => {
+                self.lemma = to_scheme(basic.pratipadika().text(), scheme);
+            }
+            PratipadikaEntry::Krdanta(k) => {
+                self.set_dhatu(k.dhatu_entry().dhatu(), scheme);
+                self.krt = Some(k.krt().to_string());
+            }
+        }
+    }
+
+    fn set_dhatu(&mut self, dhatu: &Dhatu, scheme: Scheme) {
+        self.lemma = to_scheme(dhatu.aupadeshika().unwrap_or_default(), scheme);
+        self.gana = dhatu.gana().map(|g| to_scheme(gana_name(g), scheme));
+        self.gana_id = dhatu.gana().map(|g| format!("{:?}", g));
+        self.sanadi = dhatu.sanadi().iter().map(|x| x.as_str().to_string()).collect();
+    }
+
+    /// One line summary, e.g. `गच्छन्ति: गमॢँ (भ्वादिः) Lat Kartari Prathama Bahu`.
+    pub(crate) fn summary(&self) -> String {
+        let mut parts = vec![format!("{}: {}", self.form, self.lemma)];
+        if let Some(gana) = &self.gana {
+            parts.push(format!("({})", gana));
+        }
+        if !self.sanadi.is_empty() {
+            parts.push(format!("+{}", self.sanadi.join("+")));
+        }
+        let tags = [
+            &self.krt,
+            &self.lakara,
+            &self.prayoga,
+            &self.pada,
+            &self.purusha,
+            &self.linga,
+            &self.vibhakti,
+            &self.vacana,
+        ];
+        parts.extend(tags.into_iter().flatten().cloned());
+        parts.join(" ")
+    }
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let scheme: Scheme = match &args.output_scheme {
+        Some(s) => s.parse()?,
+        None => Scheme::Devanagari,
+    };
+    let helper = PrakriyaHelper::new(&args.data.data_path);
+    let analyses = helper.analyze(
+        &args.form,
+        args.input_scheme,
+        scheme,
+        args.derive.then_some(&args.steps),
+    )?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&analyses)?);
+    } else if analyses.is_empty() {
+        println!("{}: no analysis", args.form);
+    } else {
+        for analysis in &analyses {
+            println!("{}", analysis.summary());
+            for step in &analysis.derivation {
+                println!("    {}", step);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ReportFormat {
+    Tsv,
+    Json,
+}
+
+/// Command line arguments for looking up a word list.
+#[derive(clap::Args)]
+pub(crate) struct BatchArgs {
+    /// File with one word per line, in any script. Blank lines and lines starting with `#` are
+    /// skipped.
+    pub(crate) input: PathBuf,
+
+    /// Script of the words, such as `Slp1` or `HarvardKyoto`. (Default: detected per word)
+    #[arg(long, value_parser = parse_scheme)]
+    pub(crate) input_scheme: Option<Scheme>,
+
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    #[arg(long, value_enum, default_value_t = ReportFormat::Tsv)]
+    pub(crate) format: ReportFormat,
+
+    /// Report file. (Default: stdout)
+    #[arg(long)]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Script for forms and lemmas. (Default: `Devanagari`)
+    #[arg(long)]
+    pub(crate) output_scheme: Option<String>,
+}
+
+/// The analyses of one word of a word list.
+#[derive(Debug, Serialize)]
+pub(crate) struct WordReport {
+    pub(crate) word: String,
+    pub(crate) known: bool,
+    pub(crate) analyses: Vec<Analysis>,
+}
+
+const TSV_COLUMNS: [&str; 14] = [
+    "word", "known", "kind", "lemma", "gana", "sanadi", "krt", "prayoga", "lakara", "pada",
+    "purusha", "linga", "vibhakti", "vacana",
+];
+
+/// Writes one TSV row per analysis, and a single row for an unknown word.
+fn write_tsv(reports: &[WordReport], out: impl Write) -> Result<(), Box<dyn Error>> {
+    let mut wtr = csv::WriterBuilder::new().delimiter(b'\t').from_writer(out);
+    wtr.write_record(TSV_COLUMNS)?;
+    for report in reports {
+        if report.analyses.is_empty() {
+            let mut record = vec![report.word.as_str(), "no", "unknown"];
+            record.resize(TSV_COLUMNS.len(), "");
+            wtr.write_record(record)?;
+            continue;
+        }
+        for a in &report.analyses {
+            let opt = |x: &Option<String>| x.clone().unwrap_or_default();
+            wtr.write_record([
+                report.word.clone(),
+                "yes".to_string(),
+                a.kind.to_string(),
+                a.lemma.clone(),
+                opt(&a.gana),
+                a.sanadi.join("+"),
+                opt(&a.krt),
+                opt(&a.prayoga),
+                opt(&a.lakara),
+                opt(&a.pada),
+                opt(&a.purusha),
+                opt(&a.linga),
+                opt(&a.vibhakti),
+                opt(&a.vacana),
+            ])?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+pub(crate) fn run_batch(args: BatchArgs) -> Result<(), Box<dyn Error>> {
+    let scheme: Scheme = match &args.output_scheme {
+        Some(s) => s.parse()?,
+        None => Scheme::Devanagari,
+    };
+    let helper = PrakriyaHelper::new(&args.data.data_path);
+
+    let content = fs::read_to_string(&args.input)?;
+    let reports: Vec<WordReport> = content
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(|word| {
+            let analyses = helper.analyze(word, args.input_scheme, scheme, None)?;
+            Ok(WordReport {
+                word: word.to_string(),
+                known: !analyses.is_empty(),
+                analyses,
+            })
+        })
+        .collect::<Result<_, Box<dyn Error>>>()?;
+
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(io::BufWriter::new(io::stdout())),
+    };
+    match args.format {
+        ReportFormat::Tsv => write_tsv(&reports, out)?,
+        ReportFormat::Json => {
+            let mut out = out;
+            serde_json::to_writer_pretty(&mut out, &reports)?;
+            writeln!(out)?;
+            out.flush()?;
+        }
+    }
+
+    let num_unknown = reports.iter().filter(|x| !x.known).count();
+    info!("Looked up {} words, {} unknown.", reports.len(), num_unknown);
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! Identifies the meter of verses with vidyut-chandas, and shows the laghu/guru scansion of each
+//! pAda along with where it departs from the meter.
+//!
+//! pAdas are separated by daNDas or newlines. A Markdown file is read as one verse per block of
+//! non-blank lines; headings are skipped and blockquote markers are stripped.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- chandas "धर्मक्षेत्रे कुरुक्षेत्रे समवेता युयुत्सवः। मामकाः पाण्डवाश्चैव किमकुर्वत सञ्जय॥"
+//!     cargo run --release -- chandas --input gita.md --format json
+use clap::ValueEnum;
+use log::info;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use vidyut_chandas::{Chandas, MatchType, Padya, VrttaWeight, Weight};
+use vidyut_lipi::Scheme;
+
+use crate::prakriyaa::DataArgs;
+use crate::util::{parse_scheme, to_scheme, to_slp_from};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    Text,
+    Json,
+}
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Verse to scan, in any script. Ignored if `--input` is given.
+    pub(crate) verse: Option<String>,
+
+    /// Markdown (or plain text) file of verses, separated by blank lines.
+    #[arg(long)]
+    pub(crate) input: Option<PathBuf>,
+
+    /// Script of the text, such as `Slp1` or `HarvardKyoto`. (Default: detected, which can
+    /// misread ASCII text)
+    #[arg(long, value_parser = parse_scheme)]
+    pub(crate) input_scheme: Option<Scheme>,
+
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub(crate) format: Format,
+
+    /// Script for verse text. (Default: `Devanagari`)
+    #[arg(long)]
+    pub(crate) output_scheme: Option<String>,
+}
+
+/// The scansion of one pAda.
+#[derive(Debug, Serialize)]
+pub(crate) struct PaadaReport {
+    pub(crate) text: String,
+    /// One `L` or `G` per akShara.
+    pub(crate) scansion: String,
+    /// The pattern the meter expects for this pAda (`.` for either weight), if it's a vRtta.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) expected: Option<String>,
+    /// 1-based positions of akSharas whose weight differs from `expected`. A pAda of the wrong
+    /// length has its extra or missing positions listed too.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) mismatches: Vec<usize>,
+}
+
+/// What vidyut-chandas makes of a verse.
+#[derive(Debug, Serialize)]
+pub(crate) struct VerseReport {
+    pub(crate) text: String,
+    /// Name of the vRtta or jAti, if one was identified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) meter: Option<String>,
+    /// `vrtta`, `jati` or `unknown`.
+    pub(crate) kind: &'static str,
+    /// How much of the verse fits the meter: `full`, `prefix`, `pada` or `none`.
+    pub(crate) match_type: &'static str,
+    pub(crate) paadas: Vec<PaadaReport>,
+}
+
+impl VerseReport {
+    /// Whether the whole verse fits the identified meter.
+    pub(crate) fn is_clean(&self) -> bool {
+        self.match_type == "full" && self.paadas.iter().all(|p| p.mismatches.is_empty())
+    }
+}
+
+/// Loads the meters that vidyut ships with its data build.
+pub(crate) fn load_chandas(args: &DataArgs) -> Result<Chandas, Box<dyn Error>> {
+    Ok(Chandas::from_file(args.data_path.join("data/meters.tsv"))?)
+}
+
+/// Splits a verse (in `input_scheme`, or else in whatever script is detected) into SLP1 pAdas.
+/// The verse is split before it's transliterated, since daNDas don't survive transliteration into
+/// SLP1. Verse numbers between daNDas are dropped.
+fn split_paadas(verse: &str, input_scheme: Option<Scheme>) -> Vec<String> {
+    verse
+        .split(|c| matches!(c, '\n' | '|' | '।' | '॥'))
+        .map(|x| to_slp_from(x.trim(), input_scheme))
+        .filter(|x| !x.is_empty() && !x.chars().all(|c| c.is_numeric() || c.is_whitespace()))
+        .collect()
+}
+
+fn expected_pattern(weights: &[VrttaWeight]) -> String {
+    weights
+        .iter()
+        .map(|w| match w {
+            VrttaWeight::G => 'G',
+            VrttaWeight::L => 'L',
+            _ => '.',
+        })
+        .collect()
+}
+
+fn find_mismatches(scansion: &str, expected: &str) -> Vec<usize> {
+    let (actual, expected): (Vec<_>, Vec<_>) = (scansion.chars().collect(), expected.chars().collect());
+    (0..actual.len().max(expected.len()))
+        .filter(|&i| match (actual.get(i), expected.get(i)) {
+            (Some(a), Some(e)) => *e != '.' && a != e,
+            _ => true,
+        })
+        .map(|i| i + 1)
+        .collect()
+}
+
+/// Identifies the meter of `verse`, which is in `input_scheme` or else in whatever script is
+/// detected.
+pub(crate) fn scan_verse(
+    chandas: &Chandas,
+    verse: &str,
+    input_scheme: Option<Scheme>,
+    scheme: Scheme,
+) -> VerseReport {
+    let paadas = split_paadas(verse, input_scheme);
+    let text = to_scheme(paadas.join(" | "), scheme);
+    let result = chandas.classify(paadas.join("\n"));
+
+    let (meter, kind, expected) = match result.padya() {
+        Some(Padya::Vrtta(v)) => (
+            Some(v.name().to_string()),
+            "vrtta",
+            v.padas().iter().map(|p| expected_pattern(p.weights())).collect(),
+        ),
+        Some(Padya::Jati(j)) => (Some(j.name().to_string()), "jati", vec![]),
+        None => (None, "unknown", vec![]),
+    };
+    let match_type = match result.match_type() {
+        MatchType::Full => "full",
+        MatchType::Prefix => "prefix",
+        MatchType::Pada => "pada",
+        _ => "none",
+    };
+
+    let paadas = paadas
+        .iter()
+        .zip(result.aksharas())
+        .enumerate()
+        .map(|(i, (text, aksharas))| {
+            let scansion: String = aksharas
+                .iter()
+                .map(|a| match a.weight() {
+                    Weight::G => 'G',
+                    Weight::L => 'L',
+                })
+                .collect();
+            // A vRtta's pattern is given per pAda, and repeats for samavRttas.
+            let expected = (!expected.is_empty()).then(|| expected[i % expected.len()].clone());
+            let mismatches = match &expected {
+                Some(e) => find_mismatches(&scansion, e),
+                None => vec![],
+            };
+            PaadaReport {
+                text: to_scheme(text, scheme),
+                scansion,
+                expected,
+                mismatches,
+            }
+        })
+        .collect();
+
+    VerseReport {
+        text,
+        meter,
+        kind,
+        match_type,
+        paadas,
+    }
+}
+
+/// Verses of a Markdown file: blocks of non-blank lines, without headings or blockquote markers.
+pub(crate) fn markdown_verses(content: &str) -> Vec<String> {
+    let mut verses = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('>').trim();
+        if line.is_empty() || line.starts_with('#') {
+            if !current.is_empty() {
+                verses.push(current.join("\n"));
+                current.clear();
+            }
+            continue;
+        }
+        current.push(line);
+    }
+    if !current.is_empty() {
+        verses.push(current.join("\n"));
+    }
+    verses
+}
+
+fn print_report(report: &VerseReport) {
+    println!("{}", report.text);
+    println!(
+        "    {} ({}, {})",
+        report.meter.as_deref().unwrap_or("?"),
+        report.kind,
+        report.match_type
+    );
+    for paada in &report.paadas {
+        let mut line = format!("    {}", paada.scansion);
+        if let Some(expected) = &paada.expected {
+            line.push_str(&format!(" / {}", expected));
+        }
+        if !paada.mismatches.is_empty() {
+            let positions: Vec<_> = paada.mismatches.iter().map(|x| x.to_string()).collect();
+            line.push_str(&format!("  mismatch at {}", positions.join(", ")));
+        }
+        println!("{}", line);
+    }
+    println!();
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let scheme: Scheme = match &args.output_scheme {
+        Some(s) => s.parse()?,
+        None => Scheme::Devanagari,
+    };
+    let verses = match (&args.input, &args.verse) {
+        (Some(path), _) => markdown_verses(&fs::read_to_string(path)?),
+        (None, Some(verse)) => vec![verse.clone()],
+        (None, None) => return Err("Give a verse or --input".into()),
+    };
+    let chandas = load_chandas(&args.data)?;
+
+    let reports: Vec<_> = verses
+        .iter()
+        .map(|v| scan_verse(&chandas, v, args.input_scheme, scheme))
+        .collect();
+    match args.format {
+        Format::Text => reports.iter().for_each(print_report),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+    }
+    let num_clean = reports.iter().filter(|r| r.is_clean()).count();
+    info!("{} of {} verses fit their meter.", num_clean, reports.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn devanagari_anushtubh_splits_on_dandas() {
+        let verse = "धर्मक्षेत्रे कुरुक्षेत्रे समवेता युयुत्सवः।\nमामकाः पाण्डवाश्चैव किमकुर्वत सञ्जय॥ १॥";
+        assert_eq!(
+            split_paadas(verse, None),
+            vec![
+                "Darmakzetre kurukzetre samavetA yuyutsavaH",
+                "mAmakAH pARqavAScEva kimakurvata saYjaya",
+            ]
+        );
+    }
+
+    #[test]
+    fn slp1_splits_on_bars_and_newlines() {
+        let verse = "rAmo rAjamaRiH sadA vijayate | rAmaM rameSaM Baje ||\n1\nrAmeRABihatA";
+        assert_eq!(
+            split_paadas(verse, Some(Scheme::Slp1)),
+            vec!["rAmo rAjamaRiH sadA vijayate", "rAmaM rameSaM Baje", "rAmeRABihatA"]
+        );
+    }
+
+    #[test]
+    fn missing_meters_is_an_error() {
+        let args = DataArgs {
+            data_path: PathBuf::from("no-such-vidyut-data"),
+        };
+        assert!(load_chandas(&args).is_err());
+    }
+
+    #[test]
+    fn mismatches_include_length() {
+        assert_eq!(find_mismatches("LGGL", "L.GG"), vec![4]);
+        assert_eq!(find_mismatches("LG", "LGG"), vec![3]);
+    }
+}
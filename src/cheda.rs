@@ -0,0 +1,226 @@
+//! Splits running text into padas with vidyut-cheda, and tags each pada with its lemma and
+//! grammatical categories from the kosha.
+//!
+//! The chedaka is loaded through `PrakriyaHelper`, which then looks words up in the chedaka's own
+//! kosha, so tags agree with those of `lookup` and the kosha is loaded only once.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- analyze "रामो वनं गच्छति"
+//!     cargo run --release -- analyze --input sentences.txt --format json
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use vidyut_lipi::Scheme;
+
+use crate::analysis::Analysis;
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::util::{parse_scheme, to_slp_from};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    Conllu,
+    Json,
+}
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Sentence to analyze, in any script. Ignored if `--input` is given.
+    pub(crate) text: Option<String>,
+
+    /// File with one sentence per line, in any script. Blank lines are skipped.
+    #[arg(long)]
+    pub(crate) input: Option<PathBuf>,
+
+    /// Script of the text, such as `Slp1` or `HarvardKyoto`. (Default: detected, which can
+    /// misread ASCII text)
+    #[arg(long, value_parser = parse_scheme)]
+    pub(crate) input_scheme: Option<Scheme>,
+
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    #[arg(long, value_enum, default_value_t = Format::Conllu)]
+    pub(crate) format: Format,
+
+    /// Script for forms and lemmas. (Default: `Devanagari`)
+    #[arg(long)]
+    pub(crate) output_scheme: Option<String>,
+}
+
+/// A sentence and its tagged padas, in order.
+#[derive(Debug, Serialize)]
+pub(crate) struct Sentence {
+    pub(crate) text: String,
+    pub(crate) tokens: Vec<Analysis>,
+}
+
+/// Segments `text` (in `input_scheme`, or else in whatever script is detected) and tags each pada.
+/// Padas that the kosha doesn't know are kept, with kind `unknown`.
+pub(crate) fn analyze_sentence(
+    helper: &PrakriyaHelper,
+    text: &str,
+    input_scheme: Option<Scheme>,
+    scheme: Scheme,
+) -> Result<Sentence, Box<dyn Error>> {
+    let tokens = helper
+        .chedaka()?
+        .run(&to_slp_from(text, input_scheme))?
+        .iter()
+        .map(|token| Analysis::new(token.text(), token.info(), scheme))
+        .collect();
+    Ok(Sentence {
+        text: text.to_string(),
+        tokens,
+    })
+}
+
+/// UPOS tag for an analysis.
+fn upos(analysis: &Analysis) -> &'static str {
+    match analysis.kind {
+        "subanta" => "NOUN",
+        "tinanta" => "VERB",
+        "avyaya" => "ADV",
+        _ => "X",
+    }
+}
+
+/// A CoNLL-U feature value, which must be ASCII letters and digits starting with a capital or
+/// digit. SLP1 names such as `kta` or `yaN` only need their first letter raised and any accent or
+/// nasal marks dropped.
+fn feat_value(x: &str) -> String {
+    let mut value: String = x.chars().filter(char::is_ascii_alphanumeric).collect();
+    if let Some(first) = value.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    value
+}
+
+/// CoNLL-U FEATS, sorted by name and with multiple values sorted and joined by `,` as the format
+/// requires, or `_` if there are none.
+fn feats(analysis: &Analysis) -> String {
+    let mut feats = vec![
+        ("Gana", &analysis.gana_id),
+        ("Krt", &analysis.krt),
+        ("Lakara", &analysis.lakara),
+        ("Linga", &analysis.linga),
+        ("Pada", &analysis.pada),
+        ("Prayoga", &analysis.prayoga),
+        ("Purusha", &analysis.purusha),
+        ("Vacana", &analysis.vacana),
+        ("Vibhakti", &analysis.vibhakti),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some(format!("{}={}", name, feat_value(value.as_ref()?))))
+    .collect::<Vec<_>>();
+    if !analysis.sanadi.is_empty() {
+        let mut values: Vec<_> = analysis.sanadi.iter().map(|x| feat_value(x)).collect();
+        values.sort();
+        values.dedup();
+        feats.push(format!("Sanadi={}", values.join(",")));
+        feats.sort();
+    }
+    if feats.is_empty() {
+        "_".to_string()
+    } else {
+        feats.join("|")
+    }
+}
+
+/// Writes sentences in CoNLL-U, one token per line. HEAD and DEPREL are left empty, since we
+/// don't parse dependencies.
+fn write_conllu(sentences: &[Sentence], out: &mut impl Write) -> io::Result<()> {
+    for (i, sentence) in sentences.iter().enumerate() {
+        writeln!(out, "# sent_id = {}", i + 1)?;
+        writeln!(out, "# text = {}", sentence.text)?;
+        for (j, token) in sentence.tokens.iter().enumerate() {
+            let lemma = if token.lemma.is_empty() { "_" } else { &token.lemma };
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t_\t_\t_\t_",
+                j + 1,
+                token.form,
+                lemma,
+                upos(token),
+                token.kind,
+                feats(token)
+            )?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let scheme: Scheme = match &args.output_scheme {
+        Some(s) => s.parse()?,
+        None => Scheme::Devanagari,
+    };
+    let content = match (&args.input, &args.text) {
+        (Some(path), _) => fs::read_to_string(path)?,
+        (None, Some(text)) => text.clone(),
+        (None, None) => return Err("Give a sentence or --input".into()),
+    };
+    let helper = PrakriyaHelper::new(&args.data.data_path);
+
+    let sentences = content
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|line| analyze_sentence(&helper, line, args.input_scheme, scheme))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut out = io::BufWriter::new(io::stdout());
+    match args.format {
+        Format::Conllu => write_conllu(&sentences, &mut out)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &sentences)?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feats_are_valid_conllu() {
+        let analysis = Analysis {
+            kind: "subanta",
+            gana: Some("भ्वादिः".to_string()),
+            gana_id: Some("Bhvadi".to_string()),
+            krt: Some("kta".to_string()),
+            sanadi: vec!["san".to_string(), "Ric".to_string()],
+            linga: Some("Pum".to_string()),
+            vibhakti: Some("Prathama".to_string()),
+            vacana: Some("Eka".to_string()),
+            ..Default::default()
+        };
+        let feats = feats(&analysis);
+        assert_eq!(
+            feats,
+            "Gana=Bhvadi|Krt=Kta|Linga=Pum|Sanadi=Ric,San|Vacana=Eka|Vibhakti=Prathama"
+        );
+        for feat in feats.split('|') {
+            let (_, values) = feat.split_once('=').unwrap();
+            for value in values.split(',') {
+                assert!(value.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit()));
+                assert!(value.chars().all(|c| c.is_ascii_alphanumeric()));
+            }
+        }
+    }
+
+    #[test]
+    fn feat_values_drop_marks() {
+        assert_eq!(feat_value("Sa~"), "Sa");
+        assert_eq!(feat_value("yaN"), "YaN");
+        assert_eq!(feats(&Analysis::default()), "_");
+    }
+}
@@ -0,0 +1,62 @@
+//! The command line interface of the `airaavata` binary.
+use clap::{Parser, Subcommand};
+use std::error::Error;
+
+use crate::dicts;
+use crate::{analysis, chandas, cheda, compare, pages, paradigms, repl, serve};
+
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists tinantas as CSV, SQLite or Parquet.
+    Tinantas(dicts::create_all_tinantas::Args),
+    /// Lists subantas of kosha prAtipadikas as CSV, SQLite or Parquet.
+    Subantas(dicts::create_all_subantas::Args),
+    /// Lists kRdantas of dhAtupATha dhAtus as CSV, SQLite or Parquet.
+    Krdantas(dicts::create_all_krdantas::Args),
+    /// Writes Babylon dictionaries of subantas, taddhitAntas, sanAdi or nAmadhAtu forms.
+    Babylon(dicts::babylon::Args),
+    /// Shows every kosha analysis of an inflected form.
+    Lookup(analysis::Args),
+    /// Reports kosha analyses for each word of a word list, marking unknown words.
+    BatchLookup(analysis::BatchArgs),
+    /// Splits running text into padas and tags each with its lemma and categories.
+    Analyze(cheda::Args),
+    /// Identifies the meter of verses and shows the scansion of each pAda.
+    Chandas(chandas::Args),
+    /// Serves lookups, derivations, paradigms and transliteration as JSON over localhost HTTP.
+    Serve(serve::Args),
+    /// Starts an interactive shell for exploring analyses and prakriyAs.
+    Repl(repl::Args),
+    /// Writes prakriyA Markdown pages for the vishvAsa site.
+    Pages(pages::Args),
+    /// Compares two prakriyAs step by step.
+    Compare(compare::Args),
+    /// Checks derived tinantas and subantas against reference paradigms.
+    CheckParadigms(paradigms::Args),
+}
+
+/// Parses the command line and runs the subcommand.
+pub fn run() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Tinantas(args) => dicts::create_all_tinantas::run(args),
+        Command::Subantas(args) => dicts::create_all_subantas::run(args),
+        Command::Krdantas(args) => dicts::create_all_krdantas::run(args),
+        Command::Babylon(args) => dicts::babylon::run(args),
+        Command::Lookup(args) => analysis::run(args),
+        Command::BatchLookup(args) => analysis::run_batch(args),
+        Command::Analyze(args) => cheda::run(args),
+        Command::Chandas(args) => chandas::run(args),
+        Command::Serve(args) => serve::run(args),
+        Command::Repl(args) => repl::run(args),
+        Command::Pages(args) => pages::run(args),
+        Command::Compare(args) => compare::run(args),
+        Command::CheckParadigms(args) => paradigms::run(args),
+    }
+}
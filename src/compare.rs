@@ -0,0 +1,418 @@
+//! Compares two prakriyAs step by step: where they diverge, and which optional rules were decided
+//! differently.
+//!
+//! Each side is a form (in any script) or a pada specification as in `pada.rs`. Add `variant=N`
+//! to pick among the optional variants of a specification (default 0), or give the form of the
+//! variant instead. To compare across vidyut versions, `--save` a derivation with one version and
+//! compare `--against` it with another.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- compare "dhatu=BU gana=Bhvadi lakara=Lit purusha=Prathama vacana=Eka variant=0" "... variant=1"
+//!     cargo run --release -- compare बभूव --save baBUva.json
+//!     cargo run --release -- compare बभूव --against baBUva.json
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use vidyut_prakriya::{Decision, Prakriya};
+
+use crate::pada::{parse_params, PadaSpec};
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::steps::{Step, StepOptions};
+use crate::util::{dev, to_slp};
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// The first derivation.
+    pub(crate) left: String,
+
+    /// The second derivation. Not needed with `--against`.
+    pub(crate) right: Option<String>,
+
+    /// Compare with a derivation saved by `--save` instead of `right`.
+    #[arg(long)]
+    pub(crate) against: Option<PathBuf>,
+
+    /// Save the first derivation as JSON.
+    #[arg(long)]
+    pub(crate) save: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    #[command(flatten)]
+    pub(crate) steps: StepOptions,
+
+    /// Print JSON instead of text.
+    #[arg(long)]
+    pub(crate) json: bool,
+}
+
+/// A decision on an optional rule.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RuleChoice {
+    pub(crate) rule: String,
+    pub(crate) accepted: bool,
+}
+
+/// A prakriyA as compared, and as saved with `--save`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SavedPrakriya {
+    /// The derived form, in Devanagari.
+    pub(crate) form: String,
+    pub(crate) steps: Vec<Step>,
+    pub(crate) choices: Vec<RuleChoice>,
+}
+
+impl SavedPrakriya {
+    pub(crate) fn new(helper: &PrakriyaHelper, p: &Prakriya, options: &StepOptions) -> Self {
+        Self {
+            form: dev(p.text()),
+            steps: helper.steps(p, options),
+            choices: p
+                .rule_choices()
+                .iter()
+                .map(|c| RuleChoice {
+                    rule: c.rule().code().to_string(),
+                    accepted: matches!(c.decision(), Decision::Accept),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RowKind {
+    /// Same rules, same result.
+    Same,
+    /// Same rules, different result.
+    Changed,
+    /// Only in the left derivation.
+    Left,
+    /// Only in the right derivation.
+    Right,
+}
+
+impl RowKind {
+    fn marker(&self) -> char {
+        match self {
+            Self::Same => '=',
+            Self::Changed => '~',
+            Self::Left => '<',
+            Self::Right => '>',
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Row {
+    pub(crate) kind: RowKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) left: Option<Step>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) right: Option<Step>,
+}
+
+/// An optional rule decided differently, or decided in only one derivation.
+#[derive(Debug, Serialize)]
+pub(crate) struct ChoiceDiff {
+    pub(crate) rule: String,
+    pub(crate) left: Option<bool>,
+    pub(crate) right: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Comparison {
+    pub(crate) left: String,
+    pub(crate) right: String,
+    /// Index into `rows` of the first row that isn't `same`.
+    pub(crate) first_divergence: Option<usize>,
+    pub(crate) rows: Vec<Row>,
+    pub(crate) choices: Vec<ChoiceDiff>,
+}
+
+/// Aligns steps by their rules, with a longest common subsequence.
+fn align(left: &[Step], right: &[Step]) -> Vec<Row> {
+    let (n, m) = (left.len(), right.len());
+    // lcs[i][j] is the length of the LCS of left[i..] and right[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if left[i].codes() == right[j].codes() {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut rows = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let row = if i < n && j < m && left[i].codes() == right[j].codes() {
+            let kind = if left[i].terms == right[j].terms {
+                RowKind::Same
+            } else {
+                RowKind::Changed
+            };
+            i += 1;
+            j += 1;
+            Row {
+                kind,
+                left: Some(left[i - 1].clone()),
+                right: Some(right[j - 1].clone()),
+            }
+        } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            i += 1;
+            Row {
+                kind: RowKind::Left,
+                left: Some(left[i - 1].clone()),
+                right: None,
+            }
+        } else {
+            j += 1;
+            Row {
+                kind: RowKind::Right,
+                left: None,
+                right: Some(right[j - 1].clone()),
+            }
+        };
+        rows.push(row);
+    }
+    rows
+}
+
+fn diff_choices(left: &[RuleChoice], right: &[RuleChoice]) -> Vec<ChoiceDiff> {
+    let find = |choices: &[RuleChoice], rule: &str| {
+        choices.iter().find(|c| c.rule == rule).map(|c| c.accepted)
+    };
+    let mut diffs: Vec<ChoiceDiff> = Vec::new();
+    for choice in left.iter().chain(right) {
+        if diffs.iter().any(|d| d.rule == choice.rule) {
+            continue;
+        }
+        let (l, r) = (find(left, &choice.rule), find(right, &choice.rule));
+        if l != r {
+            diffs.push(ChoiceDiff {
+                rule: choice.rule.clone(),
+                left: l,
+                right: r,
+            });
+        }
+    }
+    diffs
+}
+
+pub(crate) fn compare(left: &SavedPrakriya, right: &SavedPrakriya) -> Comparison {
+    let rows = align(&left.steps, &right.steps);
+    Comparison {
+        left: left.form.clone(),
+        right: right.form.clone(),
+        first_divergence: rows.iter().position(|r| r.kind != RowKind::Same),
+        rows,
+        choices: diff_choices(&left.choices, &right.choices),
+    }
+}
+
+/// Finds the prakriyA that `spec` names: a pada specification if it has a `dhatu` or
+/// `pratipadika`, else a form.
+fn select(helper: &PrakriyaHelper, spec: &str) -> Result<Prakriya, String> {
+    let params = parse_params(spec);
+    let variant = match params.get("variant") {
+        Some(x) => x.parse().map_err(|_| format!("Bad variant {}", x))?,
+        None => 0,
+    };
+    let prakriyas: Vec<Prakriya> =
+        if params.contains_key("dhatu") || params.contains_key("pratipadika") {
+            PadaSpec::from_params(&params)?.derive(&helper.v)
+        } else {
+            let form = to_slp(spec.split_whitespace().next().unwrap_or_default());
+            helper
+                .kosha()
+                .map_err(|e| e.to_string())?
+                .get_all(&form)
+                .iter()
+                .flat_map(|entry| helper.derive_entry(entry))
+                .filter(|p| p.text() == form)
+                .collect()
+        };
+    let num_prakriyas = prakriyas.len();
+    prakriyas.into_iter().nth(variant).ok_or_else(|| {
+        format!("{}: no variant {} of {} derivations", spec, variant, num_prakriyas)
+    })
+}
+
+fn print_comparison(comparison: &Comparison) {
+    println!("< {}\n> {}", comparison.left, comparison.right);
+    match comparison.first_divergence {
+        Some(i) => println!("Diverge at step {}.\n", i + 1),
+        None => println!("Same steps.\n"),
+    }
+    for (i, row) in comparison.rows.iter().enumerate() {
+        let marker = row.kind.marker();
+        match (&row.left, &row.right) {
+            (Some(left), Some(right)) if row.kind == RowKind::Changed => {
+                println!("{} {:>3} < {}", marker, i + 1, left.to_markdown());
+                println!("{} {:>3} > {}", marker, i + 1, right.to_markdown());
+            }
+            (Some(step), _) | (None, Some(step)) => {
+                println!("{} {:>3}   {}", marker, i + 1, step.to_markdown());
+            }
+            (None, None) => (),
+        }
+    }
+    if !comparison.choices.is_empty() {
+        println!("\nOptional rules decided differently:");
+        let decision = |x: Option<bool>| match x {
+            Some(true) => "accepted",
+            Some(false) => "declined",
+            None => "-",
+        };
+        for choice in &comparison.choices {
+            println!(
+                "    {}: {} / {}",
+                choice.rule,
+                decision(choice.left),
+                decision(choice.right)
+            );
+        }
+    }
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let helper = PrakriyaHelper::new(&args.data.data_path);
+    let left = SavedPrakriya::new(&helper, &select(&helper, &args.left)?, &args.steps);
+    if let Some(path) = &args.save {
+        fs::write(path, serde_json::to_string_pretty(&left)?)?;
+    }
+
+    let right = match (&args.against, &args.right) {
+        (Some(path), _) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        (None, Some(spec)) => SavedPrakriya::new(&helper, &select(&helper, spec)?, &args.steps),
+        (None, None) if args.save.is_some() => return Ok(()),
+        (None, None) => return Err("Give a second derivation or --against".into()),
+    };
+
+    let comparison = compare(&left, &right);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&comparison)?);
+    } else {
+        print_comparison(&comparison);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidyut_prakriya::Rule;
+
+    fn step(code: &'static str, terms: &[&str]) -> Step {
+        let terms = terms.iter().map(|x| x.to_string()).collect();
+        Step::new(&Rule::Ashtadhyayi(code), None, terms)
+    }
+
+    fn choice(rule: &str, accepted: bool) -> RuleChoice {
+        RuleChoice {
+            rule: rule.to_string(),
+            accepted,
+        }
+    }
+
+    fn kinds(rows: &[Row]) -> Vec<RowKind> {
+        rows.iter().map(|r| r.kind).collect()
+    }
+
+    fn saved(form: &str, steps: Vec<Step>, choices: Vec<RuleChoice>) -> SavedPrakriya {
+        SavedPrakriya {
+            form: form.to_string(),
+            steps,
+            choices,
+        }
+    }
+
+    #[test]
+    fn same_steps() {
+        let steps = vec![step("3.2.115", &["BU", "la~w"]), step("1.3.9", &["BU", "l"])];
+        let rows = align(&steps, &steps);
+        assert_eq!(kinds(&rows), vec![RowKind::Same, RowKind::Same]);
+    }
+
+    #[test]
+    fn insertion_on_the_right() {
+        let left = vec![step("3.2.115", &["BU"]), step("6.1.8", &["BU", "BU"])];
+        let right = vec![
+            step("3.2.115", &["BU"]),
+            step("6.4.88", &["BUv"]),
+            step("6.1.8", &["BU", "BU"]),
+        ];
+        let comparison = compare(&saved("a", left, vec![]), &saved("b", right, vec![]));
+        assert_eq!(kinds(&comparison.rows), vec![RowKind::Same, RowKind::Right, RowKind::Same]);
+        assert!(comparison.rows[1].left.is_none());
+        assert_eq!(comparison.first_divergence, Some(1));
+    }
+
+    #[test]
+    fn insertion_on_the_left() {
+        let left = vec![
+            step("3.2.115", &["BU"]),
+            step("6.4.88", &["BUv"]),
+            step("6.1.8", &["BU", "BU"]),
+        ];
+        let right = vec![step("3.2.115", &["BU"]), step("6.1.8", &["BU", "BU"])];
+        let rows = align(&left, &right);
+        assert_eq!(kinds(&rows), vec![RowKind::Same, RowKind::Left, RowKind::Same]);
+        assert!(rows[1].right.is_none());
+    }
+
+    #[test]
+    fn changed_terms() {
+        let left = vec![step("3.2.115", &["BU"]), step("6.1.8", &["BU", "BU"])];
+        let right = vec![step("3.2.115", &["BU"]), step("6.1.8", &["BUv", "BUv"])];
+        let comparison = compare(&saved("a", left, vec![]), &saved("b", right, vec![]));
+        assert_eq!(kinds(&comparison.rows), vec![RowKind::Same, RowKind::Changed]);
+        assert_eq!(comparison.first_divergence, Some(1));
+    }
+
+    #[test]
+    fn no_divergence() {
+        let steps = vec![step("3.2.115", &["BU"])];
+        let comparison = compare(&saved("a", steps.clone(), vec![]), &saved("a", steps, vec![]));
+        assert_eq!(comparison.first_divergence, None);
+    }
+
+    #[test]
+    fn choices_decided_differently() {
+        let left = vec![choice("6.4.88", true), choice("7.4.73", false)];
+        let right = vec![choice("6.4.88", true), choice("7.4.73", true), choice("1.2.5", false)];
+        let diffs = diff_choices(&left, &right);
+        let diffs: Vec<_> = diffs
+            .iter()
+            .map(|d| (d.rule.as_str(), d.left, d.right))
+            .collect();
+        assert_eq!(
+            diffs,
+            vec![("7.4.73", Some(false), Some(true)), ("1.2.5", None, Some(false))]
+        );
+    }
+
+    #[test]
+    fn saved_json_round_trip() {
+        let original = saved(
+            "बभूव",
+            vec![step("3.2.115", &["BU", "li~w"]), step("6.1.8", &["BU", "BU", "a"])],
+            vec![choice("6.4.88", true)],
+        );
+        let loaded: SavedPrakriya =
+            serde_json::from_str(&serde_json::to_string_pretty(&original).unwrap()).unwrap();
+        assert_eq!(loaded.form, original.form);
+        assert_eq!(loaded.choices, original.choices);
+        let comparison = compare(&original, &loaded);
+        assert_eq!(comparison.first_divergence, None);
+        assert!(comparison.choices.is_empty());
+        assert_eq!(loaded.steps[1].terms, vec!["BU", "BU", "a"]);
+    }
+}
@@ -0,0 +1,258 @@
+//! Metadata about a dhAtu, shown at the head of tiNanta and kRdanta entries.
+//!
+//! Everything here but the artha is kept in SLP1 and only transliterated when rendered, so that the
+//! header comes out in the output script. The artha may be in any script or language, so it's shown
+//! as given.
+//!
+//! The iT status and pada are inferred from the accents and it-markers of the aupadeshika form,
+//! which the rules behind them have exceptions to, so they're marked with `?` when rendered.
+use vidyut_lipi::Scheme;
+use vidyut_prakriya::args::{Dhatu, Gana};
+
+use crate::util::to_scheme;
+
+/// Whether a dhAtu takes the iT Agama before ArdhadhAtuka pratyayas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ItStatus {
+    Set,
+    Anit,
+    Vet,
+}
+
+impl ItStatus {
+    /// Infers the iT status from the accents of the aupadeshika form.
+    ///
+    /// - `ekAca upadeSe 'nudAttAt` (7.2.10): an anudAtta root vowel means aniT.
+    /// - `svarati-sUti-sUyati-DUY-Udito vA` (7.2.44): an Udit dhAtu is veT.
+    fn from_aupadeshika(aupadeshika: &str) -> Self {
+        if aupadeshika.contains("U~") {
+            return Self::Vet;
+        }
+        // `~\` marks an anudAtta it-vowel, which says nothing about iT.
+        let chars: Vec<char> = aupadeshika.chars().collect();
+        let has_anudatta_root = chars
+            .iter()
+            .enumerate()
+            .any(|(i, c)| *c == '\\' && (i == 0 || chars[i - 1] != '~'));
+        if has_anudatta_root {
+            Self::Anit
+        } else {
+            Self::Set
+        }
+    }
+
+    fn as_slp(&self) -> &'static str {
+        match self {
+            Self::Set => "sew",
+            Self::Anit => "aniw",
+            Self::Vet => "vew",
+        }
+    }
+}
+
+/// Which padas a dhAtu takes, as indicated by its it-markers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Padatva {
+    Parasmaipada,
+    Atmanepada,
+    Ubhayapada,
+}
+
+impl Padatva {
+    /// Follows `anudAttaNita Atmanepadam` (1.3.12) and `svaritaYitaH kartrabhiprAye` (1.3.72).
+    fn from_aupadeshika(aupadeshika: &str) -> Self {
+        if aupadeshika.ends_with('Y') || aupadeshika.contains("~^") {
+            Self::Ubhayapada
+        } else if aupadeshika.ends_with('N') || aupadeshika.contains("~\\") {
+            Self::Atmanepada
+        } else {
+            Self::Parasmaipada
+        }
+    }
+
+    fn as_slp(&self) -> &'static str {
+        match self {
+            Self::Parasmaipada => "parasmEpadI",
+            Self::Atmanepada => "AtmanepadI",
+            Self::Ubhayapada => "uBayapadI",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Karmakatva {
+    Sakarmaka,
+    Akarmaka,
+    Dvikarmaka,
+}
+
+impl Karmakatva {
+    pub(crate) fn from_slp(x: &str) -> Option<Self> {
+        match x.trim() {
+            "sakarmaka" | "sakarmakaH" => Some(Self::Sakarmaka),
+            "akarmaka" | "akarmakaH" => Some(Self::Akarmaka),
+            "dvikarmaka" | "dvikarmakaH" => Some(Self::Dvikarmaka),
+            _ => None,
+        }
+    }
+
+    fn as_slp(&self) -> &'static str {
+        match self {
+            Self::Sakarmaka => "sakarmakaH",
+            Self::Akarmaka => "akarmakaH",
+            Self::Dvikarmaka => "dvikarmakaH",
+        }
+    }
+}
+
+pub(crate) fn gana_name(gana: Gana) -> &'static str {
+    match gana {
+        Gana::Bhvadi => "BvAdiH",
+        Gana::Adadi => "adAdiH",
+        Gana::Juhotyadi => "juhotyAdiH",
+        Gana::Divadi => "divAdiH",
+        Gana::Svadi => "svAdiH",
+        Gana::Tudadi => "tudAdiH",
+        Gana::Rudhadi => "ruDAdiH",
+        Gana::Tanadi => "tanAdiH",
+        Gana::Kryadi => "kryAdiH",
+        Gana::Curadi => "curAdiH",
+        Gana::Kandvadi => "kaRqvAdiH",
+    }
+}
+
+/// SLP1 names for the antargaNas, falling back to vidyut's name for any we don't know.
+fn antargana_name(name: &str) -> String {
+    match name {
+        "Ghatadi" => "GawAdiH",
+        "Kutadi" => "kuwAdiH",
+        "Akusmiya" => "AkusmIyaH",
+        "Asvadiya" => "AsvadIyaH",
+        "Adhrshiya" => "ADfzIyaH",
+        other => other,
+    }
+    .to_string()
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DhaatuInfo {
+    pub(crate) aupadeshika: String,
+    /// dhAtupATha number, such as `01.0001`.
+    pub(crate) code: Option<String>,
+    pub(crate) gana: Option<Gana>,
+    pub(crate) antargana: Option<String>,
+    /// Shown as given.
+    pub(crate) artha: String,
+    pub(crate) it: ItStatus,
+    pub(crate) padatva: Padatva,
+    pub(crate) karmakatva: Option<Karmakatva>,
+}
+
+impl DhaatuInfo {
+    pub(crate) fn new(
+        dhatu: &Dhatu,
+        artha: &str,
+        code: Option<String>,
+        karmakatva: Option<Karmakatva>,
+    ) -> Self {
+        let aupadeshika = dhatu.aupadeshika().unwrap_or_default().to_string();
+        Self {
+            it: ItStatus::from_aupadeshika(&aupadeshika),
+            padatva: Padatva::from_aupadeshika(&aupadeshika),
+            gana: dhatu.gana(),
+            antargana: dhatu.antargana().map(|x| antargana_name(&x.to_string())),
+            artha: artha.to_string(),
+            code,
+            karmakatva,
+            aupadeshika,
+        }
+    }
+
+    /// Renders as e.g. `BU (01.0001 BvAdiH; sattAyAm; sew?, parasmEpadI?, akarmakaH)`.
+    pub(crate) fn render(&self, scheme: Scheme) -> String {
+        let mut place = Vec::new();
+        if let Some(code) = &self.code {
+            place.push(code.clone());
+        }
+        if let Some(gana) = self.gana {
+            place.push(gana_name(gana).to_string());
+        }
+        if let Some(antargana) = &self.antargana {
+            place.push(format!("({})", antargana));
+        }
+
+        let mut features = vec![
+            format!("{}?", self.it.as_slp()),
+            format!("{}?", self.padatva.as_slp()),
+        ];
+        if let Some(karmakatva) = self.karmakatva {
+            features.push(karmakatva.as_slp().to_string());
+        }
+
+        let mut parts = vec![to_scheme(place.join(" "), scheme)];
+        if !self.artha.is_empty() {
+            parts.push(self.artha.clone());
+        }
+        parts.push(to_scheme(features.join(", "), scheme));
+        format!("{} ({})", to_scheme(&self.aupadeshika, scheme), parts.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidyut_prakriya::args::Slp1String;
+
+    #[test]
+    fn it_status_from_accents() {
+        assert_eq!(ItStatus::from_aupadeshika("BU"), ItStatus::Set);
+        assert_eq!(ItStatus::from_aupadeshika("eDa~\\"), ItStatus::Set);
+        assert_eq!(ItStatus::from_aupadeshika("qukf\\Y"), ItStatus::Anit);
+        assert_eq!(ItStatus::from_aupadeshika("gAhU~\\"), ItStatus::Vet);
+    }
+
+    #[test]
+    fn ekac_anudatta_roots_are_anit() {
+        // An anudAtta root vowel makes a root aniT (7.2.10), whatever its it-markers.
+        assert_eq!(ItStatus::from_aupadeshika("Sru\\"), ItStatus::Anit);
+        assert_eq!(ItStatus::from_aupadeshika("qupa\\ca~^z"), ItStatus::Anit);
+        assert_eq!(Padatva::from_aupadeshika("qupa\\ca~^z"), Padatva::Ubhayapada);
+    }
+
+    #[test]
+    fn udit_roots_are_vet() {
+        // An Udit root is veT (7.2.44), even with an anudAtta it-vowel for its pada.
+        assert_eq!(ItStatus::from_aupadeshika("gupU~"), ItStatus::Vet);
+        assert_eq!(Padatva::from_aupadeshika("gupU~"), Padatva::Parasmaipada);
+        assert_eq!(ItStatus::from_aupadeshika("kzamU~\\z"), ItStatus::Vet);
+        assert_eq!(Padatva::from_aupadeshika("kzamU~\\z"), Padatva::Atmanepada);
+    }
+
+    #[test]
+    fn padatva_from_it_markers() {
+        assert_eq!(Padatva::from_aupadeshika("BU"), Padatva::Parasmaipada);
+        assert_eq!(Padatva::from_aupadeshika("eDa~\\"), Padatva::Atmanepada);
+        assert_eq!(Padatva::from_aupadeshika("qukf\\Y"), Padatva::Ubhayapada);
+        assert_eq!(Padatva::from_aupadeshika("SIN"), Padatva::Atmanepada);
+        assert_eq!(Padatva::from_aupadeshika("Bf\\Y"), Padatva::Ubhayapada);
+    }
+
+    #[test]
+    fn artha_is_shown_as_given() {
+        let dhatu = Dhatu::mula(Slp1String::from("BU").unwrap(), Gana::Bhvadi);
+        let info = DhaatuInfo::new(&dhatu, "to be", None, None);
+        assert_eq!(info.render(Scheme::Devanagari), "भू (भ्वादिः; to be; सेट्?, परस्मैपदी?)");
+        let info = DhaatuInfo::new(&dhatu, "सत्तायाम्", None, Some(Karmakatva::Akarmaka));
+        assert_eq!(
+            info.render(Scheme::Devanagari),
+            "भू (भ्वादिः; सत्तायाम्; सेट्?, परस्मैपदी?, अकर्मकः)"
+        );
+    }
+
+    #[test]
+    fn karmakatva_with_or_without_visarga() {
+        assert_eq!(Karmakatva::from_slp("akarmaka"), Some(Karmakatva::Akarmaka));
+        assert_eq!(Karmakatva::from_slp(" dvikarmakaH"), Some(Karmakatva::Dvikarmaka));
+        assert_eq!(Karmakatva::from_slp("karmakatva"), None);
+    }
+}
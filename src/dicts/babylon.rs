@@ -1,91 +1,223 @@
 use indicatif::ProgressBar;
-use log::{error, info};
-use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::fs;
+use log::{error, info, warn};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use vidyut_kosha::{Kosha};
-use vidyut_kosha::entries::{BasicPratipadikaEntry, DhatuEntry, PratipadikaEntry};
-use vidyut_lipi::{transliterate, Scheme};
-use vidyut_prakriya::args::{BaseKrt, Dhatu, DhatuPada, Gana, Krdanta, Krt, Lakara, Pada, Pratipadika, Prayoga, Purusha, Sanadi, Subanta, Taddhita, Taddhitanta, Tinanta, Vacana, Vibhakti};
-use vidyut_prakriya::{Dhatupatha, Vyakarana};
-
-use crate::util::{dev, slp};
-use crate::prakriyaa::{PrakriyaHelper};
-
-// Equivalent to OrderedSet - using HashSet for now, can be replaced with IndexSet if ordering is critical
-type OrderedSet<T> = HashSet<T>;
-
-lazy_static::lazy_static! {
-    static ref SANAADI_DICT_KRDANTA: HashMap<&'static str, Vec<Sanadi>> = {
-        let mut m = HashMap::new();
-        m.insert("vidyut-kRdanta", vec![]);
-        m.insert("vidyut-Nic-kRdanta", vec![Sanadi::Ric]);
-        m.insert("vidyut-san-kRdanta", vec![Sanadi::san]);
-        m.insert("vidyut-yaN-kRdanta", vec![Sanadi::yaN]);
-        m.insert("vidyut-yaNluk-kRdanta", vec![Sanadi::yaNluk]);
-        m.insert("vidyut-san-Nic-kRdanta", vec![Sanadi::san, Sanadi::Ric]);
-        m.insert("vidyut-Nic-san-kRdanta", vec![Sanadi::Ric, Sanadi::san]);
-        m
-    };
-
-    static ref SANAADI_DICT_TINANTA: HashMap<&'static str, Vec<Sanadi>> = {
-        let mut m = HashMap::new();
-        m.insert("vidyut-tiN", vec![]);
-        m.insert("vidyut-Nic-tiN", vec![Sanadi::Ric]);
-        m.insert("vidyut-san-tiN", vec![Sanadi::san]);
-        m.insert("vidyut-yaN-tiN", vec![Sanadi::yaN]);
-        m.insert("vidyut-yaN-luk-tiN", vec![Sanadi::yaNluk]);
-        m.insert("vidyut-san-Nic-tiN", vec![Sanadi::san, Sanadi::Ric]);
-        m.insert("vidyut-Nic-san-tiN", vec![Sanadi::Ric, Sanadi::san]);
-        m
-    };
+use vidyut_kosha::entries::{BasicPratipadikaEntry, PratipadikaEntry};
+use vidyut_lipi::Scheme;
+use vidyut_prakriya::args::{BaseKrt, Dhatu, DhatuPada, Krdanta, Lakara, Pratipadika, Prayoga, Purusha, Subanta, Taddhita, Taddhitanta, Tinanta, Vacana, Vibhakti};
+
+use crate::util::{dev, to_slp};
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::dicts::sanaadi::{SanaadiBase, SanaadiChain};
+use crate::dicts::babylon_entries::{self, OrderedSet, PadaTable, SuffixForms};
+use crate::dicts::lemmas::{self, DhatuLemma, PratipadikaLemma};
+use crate::dicts::manifest::{hash_data_files, BuildManifest, DictInputs};
+use crate::dicts::writer::{BabylonWriter, Definition, DictMetadata, DictWriter};
+
+/// Which Babylon dictionaries to write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum BabylonDicts {
+    /// Declensions of prAtipadikas, in volumes by first letter.
+    Subantas,
+    /// taddhitAntas of prAtipadikas, in volumes by first letter.
+    Taddhitaantas,
+    /// Forms of dhAtus with each sanAdi chain.
+    Sanaadi,
+    /// Forms of nAmadhAtus made from kosha prAtipadikas with each sanAdi chain.
+    Naamadhaatu,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Definition {
-    headwords: Vec<String>,
-    meaning: String,
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    pub(crate) dicts: BabylonDicts,
+
+    /// Directory holding a subdirectory per dictionary.
+    #[arg(long)]
+    pub(crate) dest_dir: PathBuf,
+
+    /// For `sanaadi` and `naamadhaatu`: `krdanta` or `tinanta`.
+    #[arg(long, default_value = "krdanta", value_parser = SanaadiDictFamily::from_name)]
+    pub(crate) family: SanaadiDictFamily,
+
+    /// For `sanaadi` and `naamadhaatu`: comma-separated chains such as `none,Nic,san,Nic-san`, or
+    /// `Nic,kyac,kyaN` for nAmadhAtus. Defaults to the chains of the published dictionaries.
+    #[arg(long)]
+    pub(crate) sanadi: Option<String>,
+
+    /// For `naamadhaatu`: comma-separated prAtipadikas, in any script. Defaults to every basic
+    /// prAtipadika of the kosha.
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) pratipadikas: Vec<String>,
+
+    /// A lemma list to build from instead of the kosha, as described in `lemmas.rs`: prAtipadikas
+    /// for `subantas` and `taddhitaantas`, dhAtus for `sanaadi`.
+    #[arg(long)]
+    pub(crate) lemmas: Option<PathBuf>,
+
+    /// Rewrite dictionaries even if they were built from the same inputs.
+    #[arg(long)]
+    pub(crate) overwrite: bool,
+}
+
+/// The kinds of sanAdi dictionaries, which differ in the forms listed per dhAtu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SanaadiDictFamily {
+    Krdanta,
+    Tinanta,
 }
 
-struct BabylonDictionary {
+impl SanaadiDictFamily {
+    /// Parses `krdanta` (or `kRdanta`) and `tinanta` (or `tiN`).
+    pub(crate) fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "krdanta" | "kRdanta" => Ok(Self::Krdanta),
+            "tinanta" | "tiN" => Ok(Self::Tinanta),
+            _ => Err(format!("Unknown dictionary family {}", name)),
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::Krdanta => "kRdanta",
+            Self::Tinanta => "tiN",
+        }
+    }
+
+    fn prayogas(&self) -> Vec<Prayoga> {
+        match self {
+            Self::Krdanta => vec![Prayoga::Kartari],
+            Self::Tinanta => vec![Prayoga::Kartari, Prayoga::Karmani],
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Krdanta => "कृदन्ताः",
+            Self::Tinanta => "तिङन्ताः",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Krdanta => "kRt derivatives",
+            Self::Tinanta => "Conjugation tables in all lakAras",
+        }
+    }
+
+    fn make_entry(&self) -> fn(&BabylonDictionary, String, OrderedSet<String>, Dhatu, Prayoga) -> Vec<Definition> {
+        match self {
+            Self::Krdanta => BabylonDictionary::get_krdanta_entry,
+            Self::Tinanta => BabylonDictionary::get_tinanta_entry,
+        }
+    }
+}
+
+const SUBANTA_VOLUMES: [(&str, &str, &str); 10] = [
+    ("a", "", "इ"),
+    ("i", "इ", "उ"),
+    ("uch", "उ", "क"),
+    ("ku", "क", "च"),
+    ("chu", "च", "ट"),
+    ("Tu", "ट", "त"),
+    ("tu1", "त", "प"),
+    ("pu", "प", "य"),
+    ("yrlv", "य", "श"),
+    ("shal", "श", "ा"),
+];
+
+const TADDHITAANTA_VOLUMES: [(&str, &str, &str); 14] = [
+    ("a", "", "इ"),
+    ("i", "इ", "उ"),
+    ("uch", "उ", "क"),
+    ("ku", "क", "च"),
+    ("chu", "च", "ट"),
+    ("Tu", "ट", "त"),
+    ("tu1", "त", "प"),
+    ("p", "प", "ब"),
+    ("b", "ब", "य"),
+    ("yr", "य", "ल"),
+    ("lv", "ल", "व"),
+    ("sh", "श", "स"),
+    ("s", "स", "ह"),
+    ("hal", "ह", "ा"),
+];
+
+/// Data files whose changes should trigger rebuilds, relative to the vidyut data directory.
+const INPUT_DATA_FILES: [&str; 4] = [
+    "data/dhatupatha.tsv",
+    "data/karmakatva.tsv",
+    "data/sutrapatha.tsv",
+    "kosha",
+];
+
+pub(crate) struct BabylonDictionary {
     p: PrakriyaHelper,
+    data_hashes: BTreeMap<String, String>,
 }
 
 impl BabylonDictionary {
-    fn new(p: PrakriyaHelper) -> Self {
-        Self { p }
+    pub(crate) fn new(p: PrakriyaHelper) -> Self {
+        let data_hashes = hash_data_files(&p.data_path, &INPUT_DATA_FILES);
+        Self { p, data_hashes }
+    }
+
+    /// Inputs of a dictionary, including the lemma list it's built from, if not the kosha.
+    fn inputs(&self, config: String, lemma_list: Option<&Path>) -> DictInputs {
+        let mut data_hashes = self.data_hashes.clone();
+        if let Some(path) = lemma_list {
+            data_hashes.extend(hash_data_files(Path::new(""), &[&path.to_string_lossy()]));
+        }
+        DictInputs {
+            data_hashes,
+            data_version: self.p.data_version.clone(),
+            vidyut_version: env!("VIDYUT_PRAKRIYA_VERSION").to_string(),
+            airaavata_version: env!("CARGO_PKG_VERSION").to_string(),
+            config,
+        }
+    }
+
+    /// The prAtipadikas of `lemma_list`, or else of the kosha.
+    fn pratipadika_lemmas(
+        &self,
+        lemma_list: Option<&Path>,
+    ) -> Result<Vec<PratipadikaLemma>, Box<dyn Error>> {
+        match lemma_list {
+            Some(path) => lemmas::read_pratipadikas(path)
+                .map_err(|err| format!("Can't read prAtipadikas: {}", err).into()),
+            None => Ok(lemmas::kosha_pratipadikas(self.p.kosha()?)),
+        }
+    }
+
+    /// Like `pratipadika_lemmas`, for dhAtus.
+    fn dhatu_lemmas(&self, lemma_list: Option<&Path>) -> Result<Vec<DhatuLemma>, Box<dyn Error>> {
+        match lemma_list {
+            Some(path) => lemmas::read_dhatus(path)
+                .map_err(|err| format!("Can't read dhAtus: {}", err).into()),
+            None => Ok(lemmas::kosha_dhatus(self.p.kosha()?)),
+        }
     }
 
 
     fn get_krdanta_entry(
         &self,
         entry_head: String,
-        mut headwords_in: OrderedSet<String>,
+        headwords_in: OrderedSet<String>,
         sanaadyanta: Dhatu, prayoga: Prayoga
     ) -> Vec<Definition> {
-        let mut entry = format!("{}<BR>", entry_head);
-
-        for krt in BaseKrt::iter() {
-            let anga = Krdanta::builder().dhatu(sanaadyanta.clone()).krt(krt).build();
-            let prakriyas = self.p.v.derive_krdantas(&anga.unwrap());
-
-            for p in prakriyas {
-                headwords_in.insert(dev(p.text()));
-                entry.push_str(&format!(
-                    "{}+{} = {}<BR>",
-                    dev("+"),
-                    dev(&krt.to_string()),
-                    dev(p.text())
-                ));
-            }
-        }
-
-        vec![Definition {
-            headwords: headwords_in.into_iter().collect(),
-            meaning: entry,
-        }]
+        let derivatives: Vec<SuffixForms> = BaseKrt::iter()
+            .map(|krt| {
+                let anga = Krdanta::builder().dhatu(sanaadyanta.clone()).krt(krt).build();
+                let prakriyas = self.p.v.derive_krdantas(&anga.unwrap());
+                let forms = prakriyas.iter().map(|p| dev(p.text())).collect();
+                (dev(&krt.to_string()), forms)
+            })
+            .collect();
+        vec![babylon_entries::krdanta_entry(&entry_head, headwords_in, &derivatives)]
     }
 
     fn get_tinanta_entry(
@@ -98,12 +230,10 @@ impl BabylonDictionary {
         let mut definitions = Vec::new();
 
         for lakara in Lakara::iter() {
-            let mut headwords = Vec::new();
-            let mut table_lines = Vec::new();
+            let mut padas = Vec::new();
 
             for parasmai_mode in &[DhatuPada::Parasmaipada, DhatuPada::Atmanepada] {
-                let mut lines = Vec::new();
-                let mut pada_headwords = Vec::new();
+                let mut forms = Vec::new();
 
                 for purusha in Purusha::iter() {
                     let mut vacana_forms = Vec::new();
@@ -112,262 +242,180 @@ impl BabylonDictionary {
                         let pada = Tinanta::builder().dhatu(sanaadyanta.clone()).prayoga(prayoga).pada(*parasmai_mode).lakara(lakara).purusha(purusha).vacana(vacana).build();
 
                         let prakriyas = self.p.v.derive_tinantas(&pada.unwrap());
-                        let forms: Vec<String> =
-                            prakriyas.iter().map(|p| dev(p.text())).collect();
-
-                        pada_headwords.extend(forms.clone());
-                        vacana_forms.push(forms.join("/ "));
+                        vacana_forms.push(prakriyas.iter().map(|p| dev(p.text())).collect());
                     }
-
-                    let purusha_line = vacana_forms.join("<BR>");
-                    lines.push(purusha_line);
-                }
-
-                if !pada_headwords.is_empty() {
-                    let mut table_head =
-                        format!("{} {}", entry_head, dev(&lakara.to_string()));
-                    if prayoga == Prayoga::Karmani {
-                        table_head.push_str(" अकर्तरि<BR><BR>");
-                    } else {
-                        table_head.push_str(&format!(" {}", dev(&parasmai_mode.to_string())));
-                    }
-                    table_lines.push(table_head);
-                    table_lines.push(lines.join("<BR>--<BR>"));
-                    headwords.extend(pada_headwords);
+                    forms.push(vacana_forms);
                 }
-            }
-
-            if !headwords.is_empty() {
-                let mut all_headwords = headwords_in.clone();
-                all_headwords.extend(headwords.into_iter());
-
-                let mut entry = table_lines.join("<BR><BR>");
-                entry = entry.replace("लृँत्", "लृँट्");
-
-                definitions.push(Definition {
-                    headwords: all_headwords.into_iter().collect(),
-                    meaning: entry,
+                padas.push(PadaTable {
+                    pada: dev(&parasmai_mode.to_string()),
+                    forms,
                 });
             }
+
+            definitions.extend(babylon_entries::tinanta_entry(
+                &entry_head,
+                &headwords_in,
+                &dev(&lakara.to_string()),
+                prayoga == Prayoga::Karmani,
+                &padas,
+            ));
         }
 
         definitions
     }
 
-    fn dump_subantas(&self, dest_dir: &Path) {
-        let dicts: HashMap<&str, (&str, &str)> = [
-            ("a", ("", "इ")),
-            ("i", ("इ", "उ")),
-            ("uch", ("उ", "क")),
-            ("ku", ("क", "च")),
-            ("chu", ("च", "ट")),
-            ("Tu", ("ट", "त")),
-            ("tu1", ("त", "प")),
-            ("pu", ("प", "य")),
-            ("yrlv", ("य", "श")),
-            ("shal", ("श", "ा")),
-        ]
-            .into_iter()
-            .collect();
-
-        for (dict_name, (border_start, border_end)) in dicts {
-            let mut definitions = Vec::new();
-            let dict_name = format!("vidyut-subanta-{}", dict_name);
-
-            let progress_bar = ProgressBar::new_spinner();
-            progress_bar.set_message(format!("Processing {}", dict_name));
-
-            for praatipadika in self.p.kosha.pratipadikas() {
-                if matches!(praatipadika, PratipadikaEntry::Krdanta(_)) {
-                    continue;
-                }
-                let basic_pratipadika = match praatipadika {
-                    PratipadikaEntry::Basic(basic) => basic,
-                    _ => panic!("Expected BasicPratipadika")
-                };                
-                
-                let praatipadika_str = dev(&basic_pratipadika.pratipadika().text());
-                if !(praatipadika_str >= border_start.to_string()
-                    && praatipadika_str < border_end.to_string())
-                {
-                    continue;
-                }
-
-                for linga in basic_pratipadika.lingas() {
-                    let mut headwords = OrderedSet::new();
-                    headwords.insert(praatipadika_str.clone());
-                    let mut lines = Vec::new();
-
-                    for vibhakti in Vibhakti::iter() {
-                        let mut vachana_entries = Vec::new();
-                        for vacana in Vacana::iter() {
-                            let pada = Subanta::builder().pratipadika(basic_pratipadika.pratipadika()).linga(*linga).vibhakti(vibhakti).vacana(vacana).build();
+    /// Writes the subanta volumes. `lemma_list` is a prAtipadika list as in `lemmas.rs`; without
+    /// it, every basic prAtipadika of the kosha is used.
+    pub(crate) fn dump_subantas(
+        &self,
+        dest_dir: &Path,
+        lemma_list: Option<&Path>,
+        overwrite: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let praatipadikas = keyed_pratipadikas(self.pratipadika_lemmas(lemma_list)?);
+        let source = lemmas::source_name(lemma_list);
+        build_volumes(
+            dest_dir,
+            "vidyut-subanta",
+            &SUBANTA_VOLUMES,
+            overwrite,
+            |config| self.inputs(config, lemma_list),
+            |border_start, border_end| {
+                DictMetadata::new(
+                    format!("विद्युत्-सुबन्ताः {}-{}", border_start, border_end),
+                    format!("Declensions (sup forms) of {} prAtipadikas, by li~Nga.", source),
+                    &self.p.data_version,
+                )
+            },
+            |volume, writer| {
+                let progress_bar = ProgressBar::new_spinner();
+                progress_bar.set_message(format!("Processing {}", volume.name));
+
+                for (praatipadika_str, lemma) in &praatipadikas {
+                    if !volume.holds(praatipadika_str) {
+                        continue;
+                    }
 
-                            let prakriyas = self.p.v.derive_subantas(&pada.unwrap());
-                            let mut forms = Vec::new();
+                    for linga in &lemma.lingas {
+                        let mut forms = Vec::new();
+                        for vibhakti in Vibhakti::iter() {
+                            let mut vacana_forms = Vec::new();
+                            for vacana in Vacana::iter() {
+                                let pada = Subanta::builder().pratipadika(lemma.pratipadika.clone()).linga(*linga).vibhakti(vibhakti).vacana(vacana).build();
 
-                            for prakriya in prakriyas {
-                                let pada_str = dev(prakriya.text());
-                                headwords.insert(pada_str.clone());
-                                forms.push(pada_str);
+                                let prakriyas = self.p.v.derive_subantas(&pada.unwrap());
+                                vacana_forms.push(prakriyas.iter().map(|p| dev(p.text())).collect());
                             }
+                            forms.push(vacana_forms);
+                        }
 
-                            let vachana_entry = forms.join(", ");
-                            vachana_entries.push(vachana_entry);
-                        };
-                        lines.push(vachana_entries.join("; "));
+                        writer.push(babylon_entries::subanta_entry(
+                            praatipadika_str,
+                            &dev(&linga.to_string()),
+                            &lemma.meaning,
+                            &forms,
+                        ))?;
                     }
-                    let linga_str = dev(&linga.to_string());
-                    let meaning = format!(
-                        "{} {}<BR>{}",
-                        praatipadika_str,
-                        &linga_str[..4.min(linga_str.len())],
-                        lines.join("<BR>")
-                    );
 
-                    definitions.push(Definition {
-                        headwords: headwords.into_iter().collect(),
-                        meaning,
-                    });
+                    progress_bar.tick();
                 }
 
-                progress_bar.tick();
-            }
-
-            progress_bar.finish_with_message(format!(
-                "Got {} definitions for {}",
-                definitions.len(),
-                dict_name
-            ));
-
-            let dest_file_path = dest_dir
-                .join(&dict_name)
-                .join(format!("{}.babylon", dict_name));
-            self.dump_babylon(&dest_file_path, &definitions);
-        }
-
+                progress_bar.finish();
+                Ok(())
+            },
+        )
     }
 
-    fn dump_taddhitaantas(&self, dest_dir: &Path, overwrite: bool) {
-        let dicts: HashMap<&str, (&str, &str)> = [
-            ("a", ("", "इ")),
-            ("i", ("इ", "उ")),
-            ("uch", ("उ", "क")),
-            ("ku", ("क", "च")),
-            ("chu", ("च", "ट")),
-            ("Tu", ("ट", "त")),
-            ("tu1", ("त", "प")),
-            ("p", ("प", "ब")),
-            ("b", ("ब", "य")),
-            ("yr", ("य", "ल")),
-            ("lv", ("ल", "व")),
-            ("sh", ("श", "स")),
-            ("s", ("स", "ह")),
-            ("hal", ("ह", "ा")),
-        ]
-            .into_iter()
-            .collect();
-
-        for (dict_name, (border_start, border_end)) in dicts {
-            let dict_name = format!("vidyut-taddhitAnta-{}", dict_name);
-            let dest_file_path = dest_dir
-                .join(&dict_name)
-                .join(format!("{}.babylon", dict_name));
-
-            if !overwrite && dest_file_path.exists() {
-                info!("Skipping {}", dict_name);
-                continue;
-            }
-
-            info!("Producing {}", dict_name);
-            let mut definitions = Vec::new();
-
-            let progress_bar = ProgressBar::new_spinner();
-            progress_bar.set_message(format!("Processing {}", dict_name));
-
-            for praatipadika in self.p.kosha.pratipadikas() {
-                if matches!(praatipadika, PratipadikaEntry::Krdanta(_)) {
-                    continue;
-                }
-                let basic_pratipadika = match praatipadika {
-                    PratipadikaEntry::Basic(basic) => basic,
-                    _ => panic!("Expected BasicPratipadika")
-                };
-
-                let praatipadika_str = dev(&basic_pratipadika.pratipadika().text());
-                if !(praatipadika_str >= border_start.to_string()
-                    && praatipadika_str < border_end.to_string())
-                {
-                    continue;
-                }
-
-                let mut headwords = OrderedSet::new();
-                headwords.insert(praatipadika_str.clone());
-                let mut lines = Vec::new();
-
-                for taddhita in Taddhita::iter() {
-                    let anga =
-                        Taddhitanta::builder().pratipadika(Pratipadika::from(basic_pratipadika.pratipadika())). taddhita(taddhita).build();
-                    let prakriyas = self.p.v.derive_taddhitantas(&anga.unwrap());
-
-                    if !prakriyas.is_empty() {
-                        let derivatives: Vec<String> =
-                            prakriyas.iter().map(|p| dev(p.text())).collect();
-
-                        headwords.extend(derivatives.clone());
-                        lines.push(format!(
-                            "+ {} = {}",
-                            dev(&taddhita.to_string()),
-                            derivatives.join(", ")
-                        ));
+    /// Writes the taddhitAnta volumes, from `lemma_list` as in `dump_subantas`.
+    pub(crate) fn dump_taddhitaantas(
+        &self,
+        dest_dir: &Path,
+        lemma_list: Option<&Path>,
+        overwrite: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let praatipadikas = keyed_pratipadikas(self.pratipadika_lemmas(lemma_list)?);
+        let source = lemmas::source_name(lemma_list);
+        build_volumes(
+            dest_dir,
+            "vidyut-taddhitAnta",
+            &TADDHITAANTA_VOLUMES,
+            overwrite,
+            |config| self.inputs(config, lemma_list),
+            |border_start, border_end| {
+                DictMetadata::new(
+                    format!("विद्युत्-तद्धितान्ताः {}-{}", border_start, border_end),
+                    format!("taddhitAnta derivatives of {} prAtipadikas.", source),
+                    &self.p.data_version,
+                )
+            },
+            |volume, writer| {
+                let progress_bar = ProgressBar::new_spinner();
+                progress_bar.set_message(format!("Processing {}", volume.name));
+
+                for (praatipadika_str, lemma) in &praatipadikas {
+                    if !volume.holds(praatipadika_str) {
+                        continue;
                     }
-                }
 
-                let linga_str = dev(
-                    &basic_pratipadika
-                        .lingas()
-                        .iter()
-                        .map(|l| l.to_string())
-                        .collect::<Vec<_>>()
-                        .join(","),
-                );
-
-                let meaning = format!(
-                    "{} {}<BR>{}",
-                    praatipadika_str,
-                    linga_str,
-                    lines.join("<BR>")
-                );
-
-                definitions.push(Definition {
-                    headwords: headwords.into_iter().collect(),
-                    meaning,
-                });
+                    let derivatives: Vec<SuffixForms> = Taddhita::iter()
+                        .map(|taddhita| {
+                            let anga =
+                                Taddhitanta::builder().pratipadika(lemma.pratipadika.clone()). taddhita(taddhita).build();
+                            let prakriyas = self.p.v.derive_taddhitantas(&anga.unwrap());
+                            let forms = prakriyas.iter().map(|p| dev(p.text())).collect();
+                            (dev(&taddhita.to_string()), forms)
+                        })
+                        .collect();
+                    let lingas: Vec<String> = lemma.lingas.iter().map(|l| dev(&l.to_string())).collect();
+
+                    writer.push(babylon_entries::taddhitaanta_entry(
+                        praatipadika_str,
+                        &lingas,
+                        &lemma.meaning,
+                        &derivatives,
+                    ))?;
 
-                progress_bar.tick();
-            }
+                    progress_bar.tick();
+                }
 
-            progress_bar.finish_with_message(format!("Got {} definitions", definitions.len()));
-            self.dump_babylon(&dest_file_path, &definitions);
-        }
+                progress_bar.finish();
+                Ok(())
+            },
+        )
     }
 
-    fn dump_sanaadi_dicts(
+    /// Writes a dictionary of `family` per sanAdi chain and prayoga. `lemma_list` is a dhAtu list as
+    /// in `lemmas.rs`; without it, every dhAtu of the kosha is used.
+    pub(crate) fn dump_sanaadi_dicts(
         &self,
         dest_dir: &Path,
-        sanaadi_dict: &HashMap<&str, Vec<Sanadi>>,
-        make_entry: fn(&BabylonDictionary, String, OrderedSet<String>, Dhatu, Prayoga) -> Vec<Definition>,
-    ) {
-        let dhatu_entries: Vec<DhatuEntry> = self.p.kosha.dhatus().collect();
-
-        for (dict_name, sanadi) in sanaadi_dict {
-            let prayogas = if sanaadi_dict == &*SANAADI_DICT_KRDANTA {
-                vec![Prayoga::Kartari]
-            } else {
-                vec![Prayoga::Kartari, Prayoga::Karmani]
-            };
+        chains: &[SanaadiChain],
+        family: SanaadiDictFamily,
+        lemma_list: Option<&Path>,
+        overwrite: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let dhatu_lemmas = self.dhatu_lemmas(lemma_list)?;
+        let source = lemmas::source_name(lemma_list);
+        let mut manifest = BuildManifest::load(dest_dir);
+        // The yaN-luk tiNanta dictionary used to be published with a hyphen.
+        let old_dir = dest_dir.join("vidyut-yaN-luk-tiN");
+        if old_dir.exists() {
+            warn!(
+                "{} is now built as vidyut-yaNluk-tiN; remove it once the new one is published.",
+                old_dir.display()
+            );
+        }
+        let make_entry = family.make_entry();
 
-            for prayoga in prayogas {
+        for chain in chains {
+            if let Err(err) = chain.validate(SanaadiBase::Dhatu) {
+                error!("Skipping {}", err);
+                continue;
+            }
+            let dict_name = chain.dict_name(family.suffix());
+            let sanadi = chain.sanaadis();
+
+            for prayoga in family.prayogas() {
                 let prayoga_suffix = if prayoga == Prayoga::Kartari {
                     ""
                 } else {
@@ -375,14 +423,30 @@ impl BabylonDictionary {
                 };
 
                 let dict_name = format!("{}{}", dict_name, prayoga_suffix);
-                let mut definitions = Vec::new();
+                let dest_file_path = dest_dir
+                    .join(&dict_name)
+                    .join(format!("{}.babylon", dict_name));
+                let inputs = self.inputs(
+                    format!("{} {} {:?}", family.suffix(), chain, prayoga),
+                    lemma_list,
+                );
+                if !overwrite && manifest.is_fresh(&dict_name, &dest_file_path, &inputs) {
+                    info!("Skipping {}", dict_name);
+                    continue;
+                }
+                let metadata = DictMetadata::new(
+                    format!("विद्युत्-{} {}", family.title(), dev(&dict_name)),
+                    format!("{} of {} dhAtus with sanAdi {}.", family.description(), source, chain),
+                    &self.p.data_version,
+                );
+                let mut writer = Box::new(BabylonWriter::new(&dest_file_path, metadata)?);
 
-                let progress_bar = ProgressBar::new(dhatu_entries.len() as u64);
+                let progress_bar = ProgressBar::new(dhatu_lemmas.len() as u64);
                 progress_bar.set_message(format!("Dhaatus {}", dict_name));
 
-                for dhatu_entry in dhatu_entries {
+                for lemma in &dhatu_lemmas {
                     let mut headwords_in = OrderedSet::new();
-                    let dhaatu = dhatu_entry.dhatu();
+                    let dhaatu = &lemma.dhatu;
                     let aupadeshika = dev(dhaatu.aupadeshika().unwrap());
 
                     // Add variations of aupadeshika
@@ -400,23 +464,19 @@ impl BabylonDictionary {
                             .to_string(),
                     );
 
-                    let mut dhatu_str = format!(
-                        "{} {} ({:?})",
-                        dhaatu.aupadeshika().unwrap(), dhatu_entry.artha, dhaatu.gana()
-                    );
+                    let mut dhatu_str = self
+                        .p
+                        .dhaatu_info(dhaatu, &lemma.artha, lemma.code.as_deref())
+                        .render(Scheme::Devanagari);
 
-                    for p in self.p.v.derive_dhatus(&dhatu_entry.dhatu().clone()) {
+                    for p in self.p.v.derive_dhatus(dhaatu) {
                         let dhatu_form = dev(p.text());
-                        if dev(dhaatu.aupadeshika().unwrap()) != dhatu_form {
+                        if aupadeshika != dhatu_form {
                             dhatu_str.push_str(&format!(" {}", dhatu_form));
                             headwords_in.insert(dhatu_form);
                         }
                     }
 
-                    if let Some(antargana) = &dhatu_entry.dhatu().antargana() {
-                        dhatu_str.push_str(&format!(" ({})", antargana));
-                    }
-
                     let sanaadyanta = dhaatu.with_sanadi(sanadi);
                     let mut sanaadi_str = String::new();
 
@@ -426,65 +486,308 @@ impl BabylonDictionary {
                         if !sanadi.is_empty() {
                             sanaadi_str = format!(
                                 " + {} = {}",
-                                sanadi
+                                dev(sanadi
                                     .iter()
                                     .map(|x| x.as_str())
                                     .collect::<Vec<_>>()
-                                    .join("+ "),
+                                    .join("+ ")),
                                 sanaadyanta_str
                             );
                         }
                     }
 
-                    let entry_head = dev(&format!("{}{}", dhatu_str, sanaadi_str));
-                    let mut definitions_d =
-                        make_entry(self, entry_head, headwords_in, sanaadyanta, prayoga);
-                    definitions.append(&mut definitions_d);
+                    let entry_head = format!("{}{}", dhatu_str, sanaadi_str);
+                    writer
+                        .push_all(make_entry(self, entry_head, headwords_in, sanaadyanta, prayoga))?;
 
                     progress_bar.inc(1);
                 }
 
                 progress_bar.finish();
-                info!("Got {} definitions.", definitions.len());
+                let count = writer.finish()?;
+                info!("Got {} definitions.", count);
+                manifest.record(&dict_name, inputs, count)?;
+            }
+        }
+        Ok(())
+    }
 
+    /// Like `dump_sanaadi_dicts`, but over nAmadhAtus made from kosha prAtipadikas.
+    ///
+    /// If `praatipadikas` (in any script) is given, only those prAtipadikas are used.
+    pub(crate) fn dump_naamadhaatu_dicts(
+        &self,
+        dest_dir: &Path,
+        praatipadikas: Option<&[String]>,
+        chains: &[SanaadiChain],
+        family: SanaadiDictFamily,
+        overwrite: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let kosha = self.p.kosha()?;
+        let mut manifest = BuildManifest::load(dest_dir);
+        let wanted: Option<HashSet<String>> =
+            praatipadikas.map(|x| x.iter().map(|p| dev(to_slp(p))).collect());
+        let basic_entries: Vec<BasicPratipadikaEntry> = kosha
+            .pratipadikas()
+            .filter_map(|x| match x {
+                PratipadikaEntry::Basic(basic) => Some(basic),
+                _ => None,
+            })
+            .filter(|x| {
+                wanted
+                    .as_ref()
+                    .map_or(true, |w| w.contains(&dev(x.pratipadika().text())))
+            })
+            .collect();
+        let make_entry = family.make_entry();
+
+        for chain in chains {
+            if let Err(err) = chain.validate(SanaadiBase::Pratipadika) {
+                error!("Skipping {}", err);
+                continue;
+            }
+            let dict_name = chain.dict_name(&format!("nAmadhAtu-{}", family.suffix()));
+            let sanaadi_names = chain
+                .sanaadis()
+                .iter()
+                .map(|x| x.as_str())
+                .collect::<Vec<_>>()
+                .join("+ ");
+
+            for prayoga in family.prayogas() {
+                let prayoga_suffix = if prayoga == Prayoga::Kartari {
+                    ""
+                } else {
+                    "-akartari"
+                };
+
+                let dict_name = format!("{}{}", dict_name, prayoga_suffix);
                 let dest_file_path = dest_dir
                     .join(&dict_name)
                     .join(format!("{}.babylon", dict_name));
-                self.dump_babylon(&dest_file_path, &definitions);
+                let inputs = self.inputs(
+                    format!("nAmadhAtu {} {} {:?} {:?}", family.suffix(), chain, prayoga, praatipadikas),
+                    None,
+                );
+                if !overwrite && manifest.is_fresh(&dict_name, &dest_file_path, &inputs) {
+                    info!("Skipping {}", dict_name);
+                    continue;
+                }
+                let metadata = DictMetadata::new(
+                    format!("विद्युत्-नामधातु-{} {}", family.title(), dev(&dict_name)),
+                    format!("{} of nAmadhAtus (sanAdi {}) from kosha prAtipadikas.", family.description(), chain),
+                    &self.p.data_version,
+                );
+                let mut writer = Box::new(BabylonWriter::new(&dest_file_path, metadata)?);
+
+                let progress_bar = ProgressBar::new(basic_entries.len() as u64);
+                progress_bar.set_message(format!("PrAtipadikas {}", dict_name));
+
+                for basic_pratipadika in &basic_entries {
+                    let praatipadika_str = dev(basic_pratipadika.pratipadika().text());
+                    let naamadhaatu =
+                        chain.naamadhaatu(Pratipadika::from(basic_pratipadika.pratipadika()));
+
+                    let mut headwords_in = OrderedSet::new();
+                    let mut naamadhaatu_strs = Vec::new();
+                    for p in self.p.v.derive_dhatus(&naamadhaatu) {
+                        let naamadhaatu_str = dev(p.text());
+                        headwords_in.insert(naamadhaatu_str.clone());
+                        naamadhaatu_strs.push(naamadhaatu_str);
+                    }
+                    if naamadhaatu_strs.is_empty() {
+                        progress_bar.inc(1);
+                        continue;
+                    }
+
+                    let entry_head = format!(
+                        "{} + {} = {}",
+                        praatipadika_str,
+                        dev(&sanaadi_names),
+                        naamadhaatu_strs.join("/ ")
+                    );
+                    writer
+                        .push_all(make_entry(self, entry_head, headwords_in, naamadhaatu, prayoga))?;
+
+                    progress_bar.inc(1);
+                }
+
+                progress_bar.finish();
+                let count = writer.finish()?;
+                info!("Got {} definitions.", count);
+                manifest.record(&dict_name, inputs, count)?;
             }
         }
+        Ok(())
     }
+}
 
-    fn dump_babylon(&self, dest_path: &Path, definitions: &[Definition]) {
-        if let Some(parent) = dest_path.parent() {
-            fs::create_dir_all(parent).unwrap();
-        }
+/// Pairs each prAtipadika with its Devanagari form, by which it's sorted into volumes.
+fn keyed_pratipadikas(lemmas: Vec<PratipadikaLemma>) -> Vec<(String, PratipadikaLemma)> {
+    lemmas.into_iter().map(|x| (dev(&x.text), x)).collect()
+}
+
+/// One volume of a dictionary split by the Devanagari key of its entries.
+struct Volume<'a> {
+    name: String,
+    start: &'a str,
+    end: &'a str,
+}
+
+impl Volume<'_> {
+    /// Whether an entry with this key belongs in this volume, whose range is `[start, end)`.
+    fn holds(&self, key: &str) -> bool {
+        key >= self.start && key < self.end
+    }
+}
 
-        let mut content = String::new();
-        for def in definitions {
-            content.push_str(&def.headwords.join("|"));
-            content.push_str("\n");
-            content.push_str(&def.meaning);
-            content.push_str("\n\n");
+/// Builds the volumes `<dict_prefix>-<suffix>` one at a time, each with its own pass of
+/// `write_volume` over the lemmas. Each volume is finished and recorded in the manifest before the
+/// next is begun, so that rerunning an interrupted build only builds the volumes it didn't finish.
+/// Volumes already built from the same inputs are left alone unless `overwrite`.
+fn build_volumes(
+    dest_dir: &Path,
+    dict_prefix: &str,
+    volumes: &[(&str, &str, &str)],
+    overwrite: bool,
+    make_inputs: impl Fn(String) -> DictInputs,
+    make_metadata: impl Fn(&str, &str) -> DictMetadata,
+    mut write_volume: impl FnMut(&Volume, &mut dyn DictWriter) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut manifest = BuildManifest::load(dest_dir);
+    for &(suffix, start, end) in volumes {
+        let volume = Volume {
+            name: format!("{}-{}", dict_prefix, suffix),
+            start,
+            end,
+        };
+        let dest_file_path = dest_dir
+            .join(&volume.name)
+            .join(format!("{}.babylon", volume.name));
+        let inputs = make_inputs(format!("{} [{}, {})", dict_prefix, start, end));
+        if !overwrite && manifest.is_fresh(&volume.name, &dest_file_path, &inputs) {
+            info!("Skipping {}", volume.name);
+            continue;
         }
 
-        fs::write(dest_path, content).unwrap();
+        info!("Producing {}", volume.name);
+        let mut writer = Box::new(BabylonWriter::new(&dest_file_path, make_metadata(start, end))?);
+        write_volume(&volume, writer.as_mut())?;
+        let count = writer.finish()?;
+        info!("Got {} definitions for {}", count, volume.name);
+        manifest.record(&volume.name, inputs, count)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let dict = BabylonDictionary::new(PrakriyaHelper::new(&args.data.data_path));
+    let lemma_list = args.lemmas.as_deref();
+    match args.dicts {
+        BabylonDicts::Subantas => dict.dump_subantas(&args.dest_dir, lemma_list, args.overwrite),
+        BabylonDicts::Taddhitaantas => {
+            dict.dump_taddhitaantas(&args.dest_dir, lemma_list, args.overwrite)
+        }
+        BabylonDicts::Sanaadi => {
+            let chains = match &args.sanadi {
+                Some(spec) => SanaadiChain::parse_list(spec, SanaadiBase::Dhatu)?,
+                None => SanaadiChain::defaults(),
+            };
+            dict.dump_sanaadi_dicts(
+                &args.dest_dir,
+                &chains,
+                args.family,
+                lemma_list,
+                args.overwrite,
+            )
+        }
+        BabylonDicts::Naamadhaatu => {
+            if lemma_list.is_some() {
+                return Err("Give nAmadhAtu prAtipadikas with --pratipadikas".into());
+            }
+            let chains = match &args.sanadi {
+                Some(spec) => SanaadiChain::parse_list(spec, SanaadiBase::Pratipadika)?,
+                None => SanaadiChain::naama_defaults(),
+            };
+            let praatipadikas = (!args.pratipadikas.is_empty()).then_some(&args.pratipadikas[..]);
+            dict.dump_naamadhaatu_dicts(
+                &args.dest_dir,
+                praatipadikas,
+                &chains,
+                args.family,
+                args.overwrite,
+            )
+        }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
 
+    const VOLUMES: [(&str, &str, &str); 2] = [("a", "", "क"), ("ku", "क", "ा")];
 
-fn main() {
-    env_logger::init();
+    fn inputs(config: String) -> DictInputs {
+        DictInputs {
+            data_hashes: BTreeMap::new(),
+            data_version: "test".to_string(),
+            vidyut_version: "test".to_string(),
+            airaavata_version: "test".to_string(),
+            config,
+        }
+    }
 
-    let dict = BabylonDictionary::new(
-        PrakriyaHelper::new(Path::new("/home/vvasuki/gitland/ambuda-org/vidyut-latest/")),
-    );
+    /// Builds `VOLUMES` with an entry per key, failing at `fail_at` if given. Also returns the
+    /// volumes that were begun.
+    fn build(
+        dest_dir: &Path,
+        keys: &[&str],
+        fail_at: Option<&str>,
+    ) -> (Result<(), Box<dyn Error>>, Vec<String>) {
+        let mut begun = Vec::new();
+        let result = build_volumes(
+            dest_dir,
+            "test",
+            &VOLUMES,
+            false,
+            inputs,
+            |start, end| DictMetadata::new(format!("test {}-{}", start, end), "", "test"),
+            |volume, writer| {
+                begun.push(volume.name.clone());
+                for key in keys.iter().filter(|x| volume.holds(x)) {
+                    if fail_at == Some(*key) {
+                        return Err("interrupted".into());
+                    }
+                    writer.push(Definition {
+                        headwords: vec![key.to_string()],
+                        meaning: String::new(),
+                    })?;
+                }
+                Ok(())
+            },
+        );
+        (result, begun)
+    }
 
-    // Uncomment the functions you want to run
-    dict.dump_sanaadi_dicts(
-        Path::new("/home/vvasuki/gitland/indic-dict/dicts/stardict-sanskrit-vyAkaraNa/kRdanta/vidyut/"),
-        &SANAADI_DICT_KRDANTA,
-        BabylonDictionary::get_krdanta_entry
-    );
+    #[test]
+    fn rerun_builds_only_unfinished_volumes() {
+        let dest_dir =
+            std::env::temp_dir().join(format!("airaavata-volumes-{}", std::process::id()));
+        fs::remove_dir_all(&dest_dir).ok();
+        let keys = ["अग्नि", "इन्द्र", "कवि", "गुरु"];
+
+        let (result, begun) = build(&dest_dir, &keys, Some("गुरु"));
+        assert!(result.is_err());
+        assert_eq!(begun, vec!["test-a", "test-ku"]);
+        assert!(dest_dir.join("test-a/test-a.babylon").exists());
+        assert!(!dest_dir.join("test-ku/test-ku.babylon").exists());
+
+        let (result, begun) = build(&dest_dir, &keys, None);
+        let finished = dest_dir.join("test-ku/test-ku.babylon").exists();
+        fs::remove_dir_all(&dest_dir).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(begun, vec!["test-ku"]);
+        assert!(finished);
+    }
 }
@@ -0,0 +1,326 @@
+//! Formatting of Babylon dictionary entries from forms that were already derived, kept apart from
+//! the derivation in `babylon.rs` so that it can be tested with synthetic forms.
+//!
+//! All text given to and returned by these functions is in Devanagari, except meanings from lemma
+//! lists, which are used as given.
+//!
+//! Golden files for representative entries are in `tests/golden/babylon`. After an intended change
+//! to the format, rewrite them with:
+//!
+//!     UPDATE_GOLDEN=1 cargo test babylon_entries
+use indexmap::IndexSet;
+
+use crate::dicts::writer::Definition;
+
+/// Headwords in the order they were found, without repeats.
+pub(crate) type OrderedSet<T> = IndexSet<T>;
+
+/// A suffix (krt or taddhita) and the forms made with it.
+pub(crate) type SuffixForms = (String, Vec<String>);
+
+/// Forms of one pada of a lakAra, by purusha, then vacana. A cell may have several variants or
+/// none.
+pub(crate) struct PadaTable {
+    /// Name of the pada, e.g. परस्मैपद.
+    pub(crate) pada: String,
+    pub(crate) forms: Vec<Vec<Vec<String>>>,
+}
+
+/// An entry listing the krdantas of a dhAtu, one line per form.
+pub(crate) fn krdanta_entry(
+    entry_head: &str,
+    mut headwords: OrderedSet<String>,
+    derivatives: &[SuffixForms],
+) -> Definition {
+    let mut entry = format!("{}<BR>", entry_head);
+    for (krt, forms) in derivatives {
+        for form in forms {
+            headwords.insert(form.clone());
+            entry.push_str(&format!("++{} = {}<BR>", krt, form));
+        }
+    }
+    Definition {
+        headwords: headwords.into_iter().collect(),
+        meaning: entry,
+    }
+}
+
+/// An entry with the tinanta tables of one lakAra, or `None` if no pada has any form.
+pub(crate) fn tinanta_entry(
+    entry_head: &str,
+    headwords_in: &OrderedSet<String>,
+    lakara: &str,
+    karmani: bool,
+    padas: &[PadaTable],
+) -> Option<Definition> {
+    let mut headwords = headwords_in.clone();
+    let mut table_lines = Vec::new();
+    let mut has_forms = false;
+
+    for pada in padas {
+        let pada_forms: Vec<&String> = pada.forms.iter().flatten().flatten().collect();
+        if pada_forms.is_empty() {
+            continue;
+        }
+        has_forms = true;
+        headwords.extend(pada_forms.into_iter().cloned());
+
+        let mut table_head = format!("{} {}", entry_head, lakara);
+        if karmani {
+            table_head.push_str(" अकर्तरि<BR><BR>");
+        } else {
+            table_head.push_str(&format!(" {}", pada.pada));
+        }
+        let lines: Vec<String> = pada
+            .forms
+            .iter()
+            .map(|vacanas| {
+                let cells: Vec<String> = vacanas.iter().map(|forms| forms.join("/ ")).collect();
+                cells.join("<BR>")
+            })
+            .collect();
+        table_lines.push(table_head);
+        table_lines.push(lines.join("<BR>--<BR>"));
+    }
+
+    has_forms.then(|| Definition {
+        headwords: headwords.into_iter().collect(),
+        meaning: table_lines.join("<BR><BR>").replace("लृँत्", "लृँट्"),
+    })
+}
+
+/// The first line of a prAtipadika entry, followed by the meaning, if any.
+fn praatipadika_head(praatipadika: &str, lingas: &str, meaning: &str) -> String {
+    let mut head = format!("{} {}", praatipadika, lingas);
+    if !meaning.is_empty() {
+        head.push_str(&format!("<BR>{}", meaning));
+    }
+    head
+}
+
+/// An entry with the declension of a prAtipadika in one li~Nga. `forms` are by vibhakti, then
+/// vacana.
+pub(crate) fn subanta_entry(
+    praatipadika: &str,
+    linga: &str,
+    meaning: &str,
+    forms: &[Vec<Vec<String>>],
+) -> Definition {
+    let mut headwords = OrderedSet::new();
+    headwords.insert(praatipadika.to_string());
+    let lines: Vec<String> = forms
+        .iter()
+        .map(|vacanas| {
+            let cells: Vec<String> = vacanas.iter().map(|forms| forms.join(", ")).collect();
+            cells.join("; ")
+        })
+        .collect();
+    headwords.extend(forms.iter().flatten().flatten().cloned());
+
+    let linga: String = linga.chars().take(4).collect();
+    Definition {
+        headwords: headwords.into_iter().collect(),
+        meaning: format!(
+            "{}<BR>{}",
+            praatipadika_head(praatipadika, &linga, meaning),
+            lines.join("<BR>")
+        ),
+    }
+}
+
+/// An entry listing the taddhitAntas of a prAtipadika. Taddhitas without forms are left out.
+pub(crate) fn taddhitaanta_entry(
+    praatipadika: &str,
+    lingas: &[String],
+    meaning: &str,
+    derivatives: &[SuffixForms],
+) -> Definition {
+    let mut headwords = OrderedSet::new();
+    headwords.insert(praatipadika.to_string());
+    let mut lines = Vec::new();
+    for (taddhita, forms) in derivatives.iter().filter(|(_, forms)| !forms.is_empty()) {
+        headwords.extend(forms.iter().cloned());
+        lines.push(format!("+ {} = {}", taddhita, forms.join(", ")));
+    }
+    Definition {
+        headwords: headwords.into_iter().collect(),
+        meaning: format!(
+            "{}<BR>{}",
+            praatipadika_head(praatipadika, &lingas.join(","), meaning),
+            lines.join("<BR>")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn strings(forms: &[&str]) -> Vec<String> {
+        forms.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// Splits `"a/b c -"` into cells of variants: vacanas by whitespace, variants by `/`, and `-`
+    /// for a cell without forms.
+    fn cells(line: &str) -> Vec<Vec<String>> {
+        line.split_whitespace()
+            .map(|cell| match cell {
+                "-" => Vec::new(),
+                _ => strings(&cell.split('/').collect::<Vec<_>>()),
+            })
+            .collect()
+    }
+
+    fn headwords(words: &[&str]) -> OrderedSet<String> {
+        words.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// Compares the entries, as written to a Babylon file, with `tests/golden/babylon/<name>`.
+    fn check_golden(name: &str, definitions: &[Definition]) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden/babylon")
+            .join(name);
+        let actual: String = definitions.iter().map(Definition::to_babylon).collect();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &actual).unwrap();
+        }
+        let expected = fs::read_to_string(&path).unwrap();
+        assert_eq!(actual, expected, "{} differs", path.display());
+    }
+
+    fn bhuu_lat() -> Vec<PadaTable> {
+        vec![
+            PadaTable {
+                pada: "परस्मैपद".to_string(),
+                forms: vec![
+                    cells("भवति भवतः भवन्ति"),
+                    cells("भवसि भवथः भवथ"),
+                    cells("भवामि भवावः भवामः"),
+                ],
+            },
+            PadaTable {
+                pada: "आत्मनेपद".to_string(),
+                forms: vec![cells("- - -"), cells("- - -"), cells("- - -")],
+            },
+        ]
+    }
+
+    #[test]
+    fn krdanta_golden() {
+        let definition = krdanta_entry(
+            "भू सत्तायाम्",
+            headwords(&["भू"]),
+            &[
+                ("क्त".to_string(), strings(&["भूत"])),
+                ("क्तवतु".to_string(), strings(&["भूतवत्"])),
+                ("तव्यत्".to_string(), strings(&["भवितव्य"])),
+                ("तुमुन्".to_string(), strings(&["भवितुम्"])),
+                ("शतृ".to_string(), strings(&["भवत्"])),
+            ],
+        );
+        check_golden("krdanta.babylon", &[definition]);
+    }
+
+    #[test]
+    fn tinanta_golden() {
+        let karmani = vec![PadaTable {
+            pada: "आत्मनेपद".to_string(),
+            forms: vec![
+                cells("भूयते भूयेते भूयन्ते"),
+                cells("भूयसे भूयेथे भूयध्वे"),
+                cells("भूये भूयावहे भूयामहे"),
+            ],
+        }];
+        let head = headwords(&["भू"]);
+        let definitions: Vec<Definition> = [
+            tinanta_entry("भू सत्तायाम्", &head, "लट्", false, &bhuu_lat()),
+            tinanta_entry("भू सत्तायाम्", &head, "लट्", true, &karmani),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        check_golden("tinanta.babylon", &definitions);
+    }
+
+    #[test]
+    fn subanta_golden() {
+        let forms = vec![
+            cells("रामः रामौ रामाः"),
+            cells("रामम् रामौ रामान्"),
+            cells("रामेण रामाभ्याम् रामैः"),
+            cells("रामाय रामाभ्याम् रामेभ्यः"),
+            cells("रामात् रामाभ्याम् रामेभ्यः"),
+            cells("रामस्य रामयोः रामाणाम्"),
+            cells("रामे रामयोः रामेषु"),
+            cells("राम रामौ रामाः"),
+        ];
+        check_golden("subanta.babylon", &[subanta_entry("राम", "पुम्", "", &forms)]);
+    }
+
+    #[test]
+    fn taddhitaanta_golden() {
+        let definition = taddhitaanta_entry(
+            "दशरथ",
+            &strings(&["पुम्"]),
+            "",
+            &[
+                ("अण्".to_string(), Vec::new()),
+                ("इञ्".to_string(), strings(&["दाशरथि"])),
+                ("मतुप्".to_string(), strings(&["दशरथवत्"])),
+            ],
+        );
+        check_golden("taddhitaanta.babylon", &[definition]);
+    }
+
+    #[test]
+    fn tinanta_variants_share_a_cell() {
+        let padas = vec![PadaTable {
+            pada: "परस्मैपद".to_string(),
+            forms: vec![cells("बभूव/भूव - -")],
+        }];
+        let definition = tinanta_entry("भू", &headwords(&["भू"]), "लिट्", false, &padas).unwrap();
+        assert_eq!(definition.meaning, "भू लिट् परस्मैपद<BR><BR>बभूव/ भूव<BR><BR>");
+        assert_eq!(definition.headwords, strings(&["भू", "बभूव", "भूव"]));
+    }
+
+    #[test]
+    fn tinanta_without_forms_has_no_entry() {
+        let padas = vec![PadaTable {
+            pada: "आत्मनेपद".to_string(),
+            forms: vec![cells("- - -")],
+        }];
+        assert!(tinanta_entry("भू", &headwords(&["भू"]), "लट्", false, &padas).is_none());
+    }
+
+    #[test]
+    fn tinanta_fixes_lrt() {
+        let padas = vec![PadaTable {
+            pada: "परस्मैपद".to_string(),
+            forms: vec![cells("भविष्यति")],
+        }];
+        let definition = tinanta_entry("भू", &headwords(&[]), "लृँत्", false, &padas).unwrap();
+        assert!(definition.meaning.starts_with("भू लृँट् "));
+    }
+
+    #[test]
+    fn subanta_linga_is_shortened_by_letters() {
+        let definition = subanta_entry("फल", "नपुंसकम्", "", &[cells("फलम् फले फलानि")]);
+        assert_eq!(definition.meaning, "फल नपुं<BR>फलम्; फले; फलानि");
+        assert_eq!(definition.headwords, strings(&["फल", "फलम्", "फले", "फलानि"]));
+    }
+
+    #[test]
+    fn meaning_follows_the_head() {
+        let definition = subanta_entry("फल", "नपुंसकम्", "fruit", &[cells("फलम् फले फलानि")]);
+        assert_eq!(definition.meaning, "फल नपुं<BR>fruit<BR>फलम्; फले; फलानि");
+        let definition = taddhitaanta_entry(
+            "दशरथ",
+            &strings(&["पुम्"]),
+            "a king",
+            &[("इञ्".to_string(), strings(&["दाशरथि"]))],
+        );
+        assert_eq!(definition.meaning, "दशरथ पुम्<BR>a king<BR>+ इञ् = दाशरथि");
+    }
+}
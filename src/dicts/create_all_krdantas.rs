@@ -0,0 +1,123 @@
+//! Creates a list of all kRdantas (as prAtipadikas) of the dhAtus in our dhatupatha, for each
+//! sanAdi chain and each kRt pratyaya.
+//!
+//! The columns follow `create_all_tinantas`: the forms come first, joined with `|`.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- krdantas --output-scheme Devanagari --gana Bhvadi --sanadi none,Nic
+use indicatif::ProgressBar;
+use log::info;
+use serde::Serialize;
+use std::error::Error;
+use std::path::PathBuf;
+use vidyut_lipi::{Lipika, Scheme};
+use vidyut_prakriya::args::{BaseKrt, Krdanta};
+use vidyut_prakriya::{Dhatupatha, Vyakarana};
+
+use crate::dicts::create_all_tinantas::{create_output_string, parse_output_scheme, DhatuFilters};
+use crate::dicts::form_table::{FormRow, FormTableWriter, OutputArgs};
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Path to vidyut's `dhatupatha.tsv`.
+    #[arg(long, default_value = "data/dhatupatha.tsv")]
+    pub(crate) dhatupatha: PathBuf,
+
+    /// If set, the output scheme to use. (Default: `Slp1`)
+    #[arg(long)]
+    pub(crate) output_scheme: Option<String>,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+
+    #[command(flatten)]
+    pub(crate) filters: DhatuFilters,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Row {
+    padas: String,
+    dhatu: String,
+    gana: &'static str,
+    number: u16,
+    sanadi: String,
+    krt: String,
+}
+
+impl FormRow for Row {
+    const TABLE: &'static str = "krdantas";
+    const COLUMNS: &'static [&'static str] = &["padas", "dhatu", "gana", "number", "sanadi", "krt"];
+    const LEMMA: &'static str = "dhatu";
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.padas.clone(),
+            self.dhatu.clone(),
+            self.gana.to_string(),
+            self.number.to_string(),
+            self.sanadi.clone(),
+            self.krt.clone(),
+        ]
+    }
+}
+
+/// Writes every kRdanta allowed by `filters` as a row of `out`.
+pub(crate) fn write_krdantas(
+    dhatupatha: &Dhatupatha,
+    filters: &DhatuFilters,
+    output_scheme: Scheme,
+    out: &mut FormTableWriter,
+) -> Result<(), Box<dyn Error>> {
+    let v = Vyakarana::builder().log_steps(false).build();
+    let mut lipika = Lipika::new();
+    let mut num_rows = 0;
+
+    let sanadi_choices = filters.sanadi_choices()?;
+    let progress_bar = ProgressBar::new((sanadi_choices.len() * dhatupatha.into_iter().count()) as u64);
+
+    for sanadis in &sanadi_choices {
+        for entry in dhatupatha {
+            progress_bar.inc(1);
+            if !filters.has_dhatu(entry.code(), entry.dhatu().gana()) {
+                continue;
+            }
+            let dhatu = entry.dhatu().clone().with_sanadi(sanadis.sanaadis());
+            let sanadi_text: Vec<_> = sanadis.sanaadis().iter().map(|x| x.as_str()).collect();
+            let sanadi_text = sanadi_text.join("-");
+
+            for krt in BaseKrt::iter() {
+                let krdanta = Krdanta::builder().dhatu(dhatu.clone()).krt(krt).build()?;
+                let prakriyas = v.derive_krdantas(&krdanta);
+                if prakriyas.is_empty() {
+                    continue;
+                }
+
+                let padas: Vec<_> = prakriyas.iter().map(|p| p.text()).collect();
+                let row = Row {
+                    padas: create_output_string(&mut lipika, padas, output_scheme),
+                    dhatu: dhatu.aupadeshika().expect("ok").to_string(),
+                    gana: dhatu.gana().expect("ok").as_str(),
+                    number: entry.number(),
+                    sanadi: sanadi_text.clone(),
+                    krt: krt.as_str().to_string(),
+                };
+                out.write(&row)?;
+                num_rows += 1;
+            }
+        }
+    }
+
+    progress_bar.finish();
+    info!("Wrote {} rows.", num_rows);
+    Ok(())
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let dhatupatha = Dhatupatha::from_path(&args.dhatupatha)?;
+    let output_scheme = parse_output_scheme(&args.output_scheme)?;
+    let mut out = FormTableWriter::new::<Row>(&args.output)?;
+    write_krdantas(&dhatupatha, &args.filters, output_scheme, &mut out)?;
+    out.finish::<Row>()
+}
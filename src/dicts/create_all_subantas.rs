@@ -0,0 +1,126 @@
+//! Creates a list of all subantas of the basic prAtipadikas in the kosha, in each of their lingas,
+//! with all 8 vibhaktis and 3 vacanas. kRdanta prAtipadikas are skipped, as in the Babylon dumps.
+//!
+//! The columns follow `create_all_tinantas`: the forms come first, joined with `|`, and are in the
+//! output scheme, while the prAtipadika stays in SLP1 like the dhAtu columns of the other tables.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- subantas --data-path vidyut-latest --output-scheme Devanagari
+use indicatif::ProgressBar;
+use log::info;
+use serde::Serialize;
+use std::error::Error;
+use vidyut_kosha::entries::PratipadikaEntry;
+use vidyut_kosha::Kosha;
+use vidyut_lipi::{Lipika, Scheme};
+use vidyut_prakriya::args::{Linga, Subanta, Vacana, Vibhakti};
+use vidyut_prakriya::Vyakarana;
+
+use crate::dicts::create_all_tinantas::{create_output_string, parse_output_scheme};
+use crate::dicts::form_table::{FormRow, FormTableWriter, OutputArgs};
+use crate::prakriyaa::DataArgs;
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    /// If set, the output scheme to use. (Default: `Slp1`)
+    #[arg(long)]
+    pub(crate) output_scheme: Option<String>,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Row {
+    padas: String,
+    /// In SLP1.
+    pratipadika: String,
+    linga: Linga,
+    vibhakti: Vibhakti,
+    vacana: Vacana,
+}
+
+impl FormRow for Row {
+    const TABLE: &'static str = "subantas";
+    const COLUMNS: &'static [&'static str] = &["padas", "pratipadika", "linga", "vibhakti", "vacana"];
+    const LEMMA: &'static str = "pratipadika";
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.padas.clone(),
+            self.pratipadika.clone(),
+            format!("{:?}", self.linga),
+            format!("{:?}", self.vibhakti),
+            format!("{:?}", self.vacana),
+        ]
+    }
+}
+
+/// Writes every subanta of every basic kosha prAtipadika as a row of `out`.
+pub(crate) fn write_subantas(
+    kosha: &Kosha,
+    output_scheme: Scheme,
+    out: &mut FormTableWriter,
+) -> Result<(), Box<dyn Error>> {
+    let v = Vyakarana::builder().log_steps(false).build();
+    let mut lipika = Lipika::new();
+    let mut num_rows = 0;
+
+    let progress_bar = ProgressBar::new_spinner();
+    progress_bar.set_message("prAtipadikas");
+
+    for entry in kosha.pratipadikas() {
+        let basic = match entry {
+            PratipadikaEntry::Basic(basic) => basic,
+            _ => continue,
+        };
+        let pratipadika_text = basic.pratipadika().text().to_string();
+
+        for linga in basic.lingas() {
+            for vibhakti in Vibhakti::iter() {
+                for vacana in Vacana::iter() {
+                    let subanta = Subanta::builder()
+                        .pratipadika(basic.pratipadika())
+                        .linga(*linga)
+                        .vibhakti(vibhakti)
+                        .vacana(vacana)
+                        .build()?;
+
+                    let prakriyas = v.derive_subantas(&subanta);
+                    if prakriyas.is_empty() {
+                        continue;
+                    }
+
+                    let padas: Vec<_> = prakriyas.iter().map(|p| p.text()).collect();
+                    let row = Row {
+                        padas: create_output_string(&mut lipika, padas, output_scheme),
+                        pratipadika: pratipadika_text.clone(),
+                        linga: *linga,
+                        vibhakti,
+                        vacana,
+                    };
+                    out.write(&row)?;
+                    num_rows += 1;
+                }
+            }
+        }
+        progress_bar.tick();
+    }
+
+    progress_bar.finish();
+    info!("Wrote {} rows.", num_rows);
+    Ok(())
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let kosha = Kosha::new(args.data.data_path.join("kosha"))?;
+    let output_scheme = parse_output_scheme(&args.output_scheme)?;
+    let mut out = FormTableWriter::new::<Row>(&args.output)?;
+    write_subantas(&kosha, output_scheme, &mut out)?;
+    out.finish::<Row>()
+}
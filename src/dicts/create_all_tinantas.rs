@@ -1,4 +1,4 @@
-//! Creates a very large list of tinantas. This list includes all combinations of:
+//! Creates a very large list of tinantas. By default, this list includes all combinations of:
 //!
 //! - Around 2000 dhatus from our dhatupatha
 //! - 2 prayogas
@@ -7,22 +7,41 @@
 //! - 3 purushas
 //! - 3 vacanas
 //!
-//! These combinations produce around 2000 x 2 x 5 x 10 x 3 x 3 = 1.8 million tinantas.
+//! These combinations produce around 2000 x 2 x 5 x 10 x 3 x 3 = 1.8 million tinantas. The
+//! filters in `Args` narrow this down to a targeted list.
+//!
 //! Usage:
 //!
-//!     cargo run --release --example create_all_tinantas -- --output-scheme Devanagari
-use clap::Parser;
+//!     cargo run --release -- tinantas --output-scheme Devanagari --gana Bhvadi --lakara Lit
+//!
+//! Pass `--jobs 0` to derive on all cores, and `--format sqlite --output forms.db` to write to a
+//! SQLite database instead of CSV.
+use indicatif::{ProgressBar, ProgressStyle};
+use log::info;
+use rayon::prelude::*;
 use serde::Serialize;
 use std::error::Error;
-use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
 use vidyut_lipi::{Lipika, Scheme};
-use vidyut_prakriya::args::{Lakara, Prayoga, Purusha, Sanadi, Tinanta, Vacana};
-use vidyut_prakriya::{Dhatupatha, Vyakarana};
+use vidyut_prakriya::args::{DhatuPada, Gana, Lakara, Prayoga, Purusha, Sanadi, Tinanta, Vacana};
+use vidyut_prakriya::{Decision, Dhatupatha, DhatupathaEntry, Prakriya, Vyakarana};
+
+use crate::dicts::sanaadi::{SanaadiBase, SanaadiChain};
+use crate::util::parse_arg;
+use crate::dicts::form_table::{FormRow, FormTableWriter, OutputArgs};
+
+/// dhAtus per thread in each batch. Bigger batches keep threads busier; smaller ones hold fewer
+/// rows in memory.
+const BATCH_SIZE_PER_THREAD: usize = 8;
 
 /// Command line arguments.
-#[derive(Parser)]
-#[command(author, version, about)]
-struct Args {
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Path to vidyut's `dhatupatha.tsv`.
+    #[arg(long, default_value = "data/dhatupatha.tsv")]
+    pub(crate) dhatupatha: PathBuf,
+
     /// If set, the output scheme to use.
     ///
     /// Any scheme name accepted by `vidyut-prakriya` is valid. Examples: `Devanagari`, `Iso15919`,
@@ -30,13 +49,148 @@ struct Args {
     ///
     /// (Default: `Slp1`)
     #[arg(long)]
-    output_scheme: Option<String>,
+    pub(crate) output_scheme: Option<String>,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+
+    /// Number of dhAtus to derive in parallel. 0 uses all cores.
+    #[arg(long, default_value_t = 1)]
+    pub(crate) jobs: usize,
+
+    /// Write one row per alternative form, with its variant index and the optional rules its
+    /// derivation went through, instead of one row per analysis with `|`-joined forms.
+    #[arg(long)]
+    pub(crate) explode: bool,
+
+    #[command(flatten)]
+    pub(crate) filters: TinantaFilters,
+}
+
+/// Restricts which dhAtus are used. An empty list means "all".
+#[derive(clap::Args, Default)]
+pub(crate) struct DhatuFilters {
+    /// gaNas to include, e.g. `Bhvadi`.
+    #[arg(long, value_delimiter = ',', value_parser = parse_arg::<Gana>)]
+    pub(crate) gana: Vec<Gana>,
+
+    /// First dhAtupATha code to include, e.g. `01.0001`.
+    #[arg(long)]
+    pub(crate) from_code: Option<String>,
+
+    /// Last dhAtupATha code to include, e.g. `01.0100`.
+    #[arg(long)]
+    pub(crate) to_code: Option<String>,
+
+    /// sanAdi chains, e.g. `none,Nic,san-Nic`. (Default: `none,san,Nic,yaN,yaNluk`)
+    #[arg(long)]
+    pub(crate) sanadi: Option<String>,
+}
+
+impl DhatuFilters {
+    pub(crate) fn sanadi_choices(&self) -> Result<Vec<SanaadiChain>, String> {
+        match &self.sanadi {
+            Some(spec) => SanaadiChain::parse_list(spec, SanaadiBase::Dhatu),
+            None => Ok([
+                vec![],
+                vec![Sanadi::san],
+                vec![Sanadi::Ric],
+                vec![Sanadi::yaN],
+                vec![Sanadi::yaNluk],
+            ]
+            .into_iter()
+            .map(|x| SanaadiChain::new(x, SanaadiBase::Dhatu).expect("valid"))
+            .collect()),
+        }
+    }
+
+    pub(crate) fn has_dhatu(&self, code: &str, gana: Option<Gana>) -> bool {
+        let gana_ok = self.gana.is_empty() || gana.map_or(false, |g| self.gana.contains(&g));
+        let from_ok = self.from_code.as_ref().map_or(true, |x| code >= x.as_str());
+        let to_ok = self.to_code.as_ref().map_or(true, |x| code <= x.as_str());
+        gana_ok && from_ok && to_ok
+    }
+}
+
+/// Restricts which tinantas are listed. An empty list means "all".
+#[derive(clap::Args, Default)]
+pub(crate) struct TinantaFilters {
+    #[command(flatten)]
+    pub(crate) dhatus: DhatuFilters,
+
+    /// lakAras to include, e.g. `Lat,Lit`.
+    #[arg(long, value_delimiter = ',', value_parser = parse_arg::<Lakara>)]
+    pub(crate) lakara: Vec<Lakara>,
+
+    /// prayogas to include. (Default: `Kartari,Karmani`)
+    #[arg(long, value_delimiter = ',', value_parser = parse_arg::<Prayoga>)]
+    pub(crate) prayoga: Vec<Prayoga>,
+
+    /// If set, derive only with this pada instead of the ones vidyut picks for each dhAtu.
+    #[arg(long, value_parser = parse_arg::<DhatuPada>)]
+    pub(crate) pada: Option<DhatuPada>,
+}
+
+impl TinantaFilters {
+    fn prayogas(&self) -> Vec<Prayoga> {
+        if self.prayoga.is_empty() {
+            vec![Prayoga::Kartari, Prayoga::Karmani]
+        } else {
+            self.prayoga.clone()
+        }
+    }
+
+    fn lakaras(&self) -> Vec<Lakara> {
+        if self.lakara.is_empty() {
+            Lakara::iter().collect()
+        } else {
+            self.lakara.clone()
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
-struct Row<'a> {
+pub(crate) struct Row {
     padas: String,
-    dhatu: &'a str,
+    dhatu: String,
+    gana: &'static str,
+    number: u16,
+    sanadi: String,
+    prayoga: Prayoga,
+    lakara: Lakara,
+    purusha: Purusha,
+    vacana: Vacana,
+}
+
+impl FormRow for Row {
+    const TABLE: &'static str = "tinantas";
+    const COLUMNS: &'static [&'static str] = &["padas", "dhatu", "gana", "number", "sanadi", "prayoga", "lakara", "purusha", "vacana"];
+    const LEMMA: &'static str = "dhatu";
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.padas.clone(),
+            self.dhatu.clone(),
+            self.gana.to_string(),
+            self.number.to_string(),
+            self.sanadi.clone(),
+            format!("{:?}", self.prayoga),
+            format!("{:?}", self.lakara),
+            format!("{:?}", self.purusha),
+            format!("{:?}", self.vacana),
+        ]
+    }
+}
+
+/// One alternative form per row, for use as a form -> analysis lookup table.
+#[derive(Debug, Serialize)]
+pub(crate) struct ExplodedRow {
+    pada: String,
+    /// Index of this form among the sorted alternatives for the same analysis.
+    variant: usize,
+    /// Optional rules the derivation passed through, as `code+` (applied) or `code-` (declined).
+    optional_rules: String,
+    dhatu: String,
     gana: &'static str,
     number: u16,
     sanadi: String,
@@ -46,7 +200,49 @@ struct Row<'a> {
     vacana: Vacana,
 }
 
-fn create_output_string(
+impl FormRow for ExplodedRow {
+    const TABLE: &'static str = "tinanta_forms";
+    const COLUMNS: &'static [&'static str] = &["pada", "variant", "optional_rules", "dhatu", "gana", "number", "sanadi", "prayoga", "lakara", "purusha", "vacana"];
+    const LEMMA: &'static str = "dhatu";
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.pada.clone(),
+            self.variant.to_string(),
+            self.optional_rules.clone(),
+            self.dhatu.clone(),
+            self.gana.to_string(),
+            self.number.to_string(),
+            self.sanadi.clone(),
+            format!("{:?}", self.prayoga),
+            format!("{:?}", self.lakara),
+            format!("{:?}", self.purusha),
+            format!("{:?}", self.vacana),
+        ]
+    }
+}
+
+/// Rows of either shape, as chosen by `--explode`.
+enum OutRow {
+    Joined(Row),
+    Exploded(ExplodedRow),
+}
+
+pub(crate) fn optional_rules(p: &Prakriya) -> String {
+    p.rule_choices()
+        .iter()
+        .map(|choice| {
+            let mark = match choice.decision() {
+                Decision::Accept => "+",
+                Decision::Decline => "-",
+            };
+            format!("{}{}", choice.rule().code(), mark)
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+pub(crate) fn create_output_string(
     lipika: &mut Lipika,
     mut items: Vec<String>,
     output_scheme: Scheme,
@@ -60,91 +256,189 @@ fn create_output_string(
     items.join("|")
 }
 
-fn run(dhatupatha: Dhatupatha, args: Args) -> Result<(), Box<dyn Error>> {
-    let sanadi_choices = vec![
-        vec![],
-        vec![Sanadi::san],
-        vec![Sanadi::Ric],
-        vec![Sanadi::yaN],
-        vec![Sanadi::yaNluk],
-    ];
+/// Everything needed to derive the rows of one dhAtu, shared across threads.
+struct Job<'a> {
+    v: Vyakarana,
+    filters: &'a TinantaFilters,
+    prayogas: Vec<Prayoga>,
+    lakaras: Vec<Lakara>,
+    output_scheme: Scheme,
+    explode: bool,
+}
 
-    let v = Vyakarana::builder().log_steps(false).build();
-    let mut lipika = Lipika::new();
-    let mut wtr = csv::Writer::from_writer(io::stdout());
+impl Job<'_> {
+    fn derive_rows(
+        &self,
+        lipika: &mut Lipika,
+        entry: &DhatupathaEntry,
+        sanadis: &SanaadiChain,
+    ) -> Result<Vec<OutRow>, String> {
+        let mut rows = Vec::new();
+        let dhatu = entry.dhatu().clone().with_sanadi(sanadis.sanaadis());
+        let sanadi_text: Vec<_> = sanadis.sanaadis().iter().map(|x| x.as_str()).collect();
+        let sanadi_text = sanadi_text.join("-");
 
-    let output_scheme: Scheme = match args.output_scheme {
-        Some(s) => s.parse()?,
-        None => Scheme::Slp1,
-    };
+        for prayoga in &self.prayogas {
+            for lakara in &self.lakaras {
+                for purusha in Purusha::iter() {
+                    for vacana in Vacana::iter() {
+                        let mut builder = Tinanta::builder();
+                        builder
+                            .dhatu(dhatu.clone())
+                            .prayoga(*prayoga)
+                            .purusha(purusha)
+                            .vacana(vacana)
+                            .lakara(*lakara);
+                        if let Some(pada) = self.filters.pada {
+                            builder.pada(pada);
+                        }
+                        let tinanta = builder.build().map_err(|e| e.to_string())?;
 
-    for sanadis in &sanadi_choices {
-        for entry in &dhatupatha {
-            let dhatu = entry.dhatu().clone().with_sanadi(&sanadis);
-            let sanadi_text: Vec<_> = sanadis.iter().map(|x| x.as_str()).collect();
-            let sanadi_text = sanadi_text.join("-");
-
-            for prayoga in [Prayoga::Kartari, Prayoga::Karmani] {
-                for lakara in Lakara::iter() {
-                    for purusha in Purusha::iter() {
-                        for vacana in Vacana::iter() {
-                            let tinanta = Tinanta::builder()
-                                .dhatu(dhatu.clone())
-                                .prayoga(prayoga)
-                                .purusha(purusha)
-                                .vacana(vacana)
-                                .lakara(lakara)
-                                .build()?;
-
-                            let prakriyas = v.derive_tinantas(&tinanta);
-                            if prakriyas.is_empty() {
-                                continue;
-                            }
+                        let prakriyas = self.v.derive_tinantas(&tinanta);
+                        if prakriyas.is_empty() {
+                            continue;
+                        }
 
-                            let dhatu_text = &dhatu.aupadeshika().expect("ok");
-                            let padas: Vec<_> = prakriyas.iter().map(|p| p.text()).collect();
-                            let padas = create_output_string(&mut lipika, padas, output_scheme);
-
-                            let row = Row {
-                                padas,
-                                dhatu: dhatu_text,
-                                gana: dhatu.gana().expect("ok").as_str(),
-                                number: entry.number(),
-                                sanadi: sanadi_text.clone(),
-                                lakara,
-                                purusha,
-                                vacana,
-                                prayoga,
-                            };
-
-                            wtr.serialize(row)?;
+                        let dhatu_text = dhatu.aupadeshika().expect("ok").to_string();
+                        let gana = dhatu.gana().expect("ok").as_str();
+
+                        if self.explode {
+                            let mut prakriyas = prakriyas;
+                            prakriyas.sort_by_key(|p| p.text());
+                            for (variant, p) in prakriyas.iter().enumerate() {
+                                rows.push(OutRow::Exploded(ExplodedRow {
+                                    pada: create_output_string(
+                                        lipika,
+                                        vec![p.text()],
+                                        self.output_scheme,
+                                    ),
+                                    variant,
+                                    optional_rules: optional_rules(p),
+                                    dhatu: dhatu_text.clone(),
+                                    gana,
+                                    number: entry.number(),
+                                    sanadi: sanadi_text.clone(),
+                                    lakara: *lakara,
+                                    purusha,
+                                    vacana,
+                                    prayoga: *prayoga,
+                                }));
+                            }
+                            continue;
                         }
+
+                        let padas: Vec<_> = prakriyas.iter().map(|p| p.text()).collect();
+                        let padas = create_output_string(lipika, padas, self.output_scheme);
+
+                        rows.push(OutRow::Joined(Row {
+                            padas,
+                            dhatu: dhatu_text,
+                            gana,
+                            number: entry.number(),
+                            sanadi: sanadi_text.clone(),
+                            lakara: *lakara,
+                            purusha,
+                            vacana,
+                            prayoga: *prayoga,
+                        }));
                     }
                 }
             }
         }
+        Ok(rows)
     }
-
-    wtr.flush()?;
-    Ok(())
 }
 
-fn main() {
-    let args = Args::parse();
-
-    let dhatus = match Dhatupatha::from_path("data/dhatupatha.tsv") {
-        Ok(res) => res,
-        Err(err) => {
-            println!("{}", err);
-            std::process::exit(1);
-        }
+/// Writes every tinanta allowed by `filters` as a row of `out`.
+///
+/// dhAtus are derived `jobs` at a time (0 means one per core) in batches, and each batch is
+/// written in dhAtupATha order, so the output is the same for any number of jobs.
+pub(crate) fn write_tinantas(
+    dhatupatha: &Dhatupatha,
+    filters: &TinantaFilters,
+    output_scheme: Scheme,
+    jobs: usize,
+    explode: bool,
+    out: &mut FormTableWriter,
+) -> Result<(), Box<dyn Error>> {
+    let sanadi_choices = filters.dhatus.sanadi_choices()?;
+    let job = Job {
+        v: Vyakarana::builder().log_steps(false).build(),
+        filters,
+        prayogas: filters.prayogas(),
+        lakaras: filters.lakaras(),
+        output_scheme,
+        explode,
     };
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    let batch_size = BATCH_SIZE_PER_THREAD * pool.current_num_threads();
+
+    let work: Vec<(&SanaadiChain, &DhatupathaEntry)> = sanadi_choices
+        .iter()
+        .flat_map(|sanadis| dhatupatha.into_iter().map(move |entry| (sanadis, entry)))
+        .filter(|(_, entry)| filters.dhatus.has_dhatu(entry.code(), entry.dhatu().gana()))
+        .collect();
+
+    let progress_bar = ProgressBar::new(work.len() as u64);
+    progress_bar.set_style(
+        ProgressStyle::with_template("{bar:40} {pos}/{len} dhAtus {per_sec} ETA {eta} {msg}")
+            .unwrap(),
+    );
+    let start = Instant::now();
+    let mut num_rows = 0;
 
-    match run(dhatus, args) {
-        Ok(()) => (),
-        Err(err) => {
-            eprintln!("{}", err);
-            std::process::exit(1);
+    for batch in work.chunks(batch_size) {
+        let batch_rows: Vec<Result<Vec<OutRow>, String>> = pool.install(|| {
+            batch
+                .par_iter()
+                .map_init(Lipika::new, |lipika, (sanadis, entry)| {
+                    job.derive_rows(lipika, entry, sanadis)
+                })
+                .collect()
+        });
+        for rows in batch_rows {
+            for row in rows? {
+                match row {
+                    OutRow::Joined(row) => out.write(&row)?,
+                    OutRow::Exploded(row) => out.write(&row)?,
+                }
+                num_rows += 1;
+            }
         }
+        progress_bar.inc(batch.len() as u64);
+        progress_bar.set_message(format!("{} rows", num_rows));
+    }
+
+    progress_bar.finish();
+    let elapsed = start.elapsed().as_secs_f64();
+    info!(
+        "Wrote {} rows for {} dhAtus in {:.1}s ({:.0} rows/s, {} threads).",
+        num_rows,
+        work.len(),
+        elapsed,
+        num_rows as f64 / elapsed.max(f64::EPSILON),
+        pool.current_num_threads()
+    );
+    Ok(())
+}
+
+pub(crate) fn parse_output_scheme(output_scheme: &Option<String>) -> Result<Scheme, Box<dyn Error>> {
+    Ok(match output_scheme {
+        Some(s) => s.parse()?,
+        None => Scheme::Slp1,
+    })
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let dhatupatha = Dhatupatha::from_path(&args.dhatupatha)?;
+    let output_scheme = parse_output_scheme(&args.output_scheme)?;
+    if args.explode {
+        let mut out = FormTableWriter::new::<ExplodedRow>(&args.output)?;
+        write_tinantas(&dhatupatha, &args.filters, output_scheme, args.jobs, true, &mut out)?;
+        out.finish::<ExplodedRow>()
+    } else {
+        let mut out = FormTableWriter::new::<Row>(&args.output)?;
+        write_tinantas(&dhatupatha, &args.filters, output_scheme, args.jobs, false, &mut out)?;
+        out.finish::<Row>()
     }
 }
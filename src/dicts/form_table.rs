@@ -0,0 +1,353 @@
+//! Writes the rows of the flat form lists (`tinantas`, `subantas`, `krdantas`) as CSV, into a
+//! SQLite database, or (with the `parquet` feature) as a Parquet file.
+//!
+//! In SQLite, each list is a table named after it, plus a `<table>_forms` table with one row per
+//! form, so that forms and lemmas can both be looked up through an index.
+use clap::ValueEnum;
+use rusqlite::{params_from_iter, Connection};
+use serde::Serialize;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Rows inserted per SQLite transaction.
+const SQLITE_BATCH_SIZE: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Csv,
+    Sqlite,
+    Parquet,
+}
+
+#[derive(clap::Args, Default)]
+pub(crate) struct OutputArgs {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    pub(crate) format: OutputFormat,
+
+    /// Output file. Required for `sqlite` and `parquet`; CSV goes to stdout if unset.
+    #[arg(long)]
+    pub(crate) output: Option<PathBuf>,
+}
+
+/// A row of one of the flat form lists.
+pub(crate) trait FormRow: Serialize {
+    /// Name of the table, e.g. `tinantas`.
+    const TABLE: &'static str;
+    /// Column names, in the order of `values`. The first column holds the `|`-joined forms.
+    const COLUMNS: &'static [&'static str];
+    /// Column holding the lemma (dhAtu or prAtipadika).
+    const LEMMA: &'static str;
+
+    fn values(&self) -> Vec<String>;
+
+    fn forms(&self) -> Vec<String> {
+        self.values()[0].split('|').map(|x| x.to_string()).collect()
+    }
+}
+
+pub(crate) enum FormTableWriter {
+    Csv(csv::Writer<Box<dyn Write>>),
+    Sqlite(SqliteTable),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet_table::ParquetTable),
+}
+
+impl FormTableWriter {
+    pub(crate) fn new<R: FormRow>(args: &OutputArgs) -> Result<Self, Box<dyn Error>> {
+        let output = || {
+            args.output
+                .clone()
+                .ok_or_else(|| format!("--output is required for {:?}", args.format))
+        };
+        Ok(match args.format {
+            OutputFormat::Csv => {
+                let out: Box<dyn Write> = match &args.output {
+                    Some(path) => Box::new(io::BufWriter::new(std::fs::File::create(path)?)),
+                    None => Box::new(io::BufWriter::new(io::stdout())),
+                };
+                Self::Csv(csv::Writer::from_writer(out))
+            }
+            OutputFormat::Sqlite => Self::Sqlite(SqliteTable::new::<R>(&output()?)?),
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => Self::Parquet(parquet_table::ParquetTable::new::<R>(&output()?)?),
+            #[cfg(not(feature = "parquet"))]
+            OutputFormat::Parquet => {
+                return Err("Parquet output needs airaavata built with `--features parquet`".into())
+            }
+        })
+    }
+
+    pub(crate) fn write<R: FormRow>(&mut self, row: &R) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Csv(w) => w.serialize(row)?,
+            Self::Sqlite(t) => t.insert(row)?,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(t) => t.push(row)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish<R: FormRow>(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Csv(mut w) => w.flush()?,
+            Self::Sqlite(t) => t.finish::<R>()?,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(t) => t.finish()?,
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct SqliteTable {
+    conn: Connection,
+    insert_row: String,
+    insert_form: String,
+    pending: usize,
+}
+
+impl SqliteTable {
+    fn new<R: FormRow>(path: &Path) -> rusqlite::Result<Self> {
+        Self::with_connection::<R>(Connection::open(path)?)
+    }
+
+    /// Replaces the tables of `R` in `conn`.
+    fn with_connection<R: FormRow>(conn: Connection) -> rusqlite::Result<Self> {
+        let table = R::TABLE;
+        let columns: Vec<_> = R::COLUMNS.iter().map(|c| format!("{} TEXT", c)).collect();
+        conn.execute_batch(&format!(
+            "PRAGMA journal_mode = OFF;
+             PRAGMA synchronous = OFF;
+             DROP TABLE IF EXISTS {table}_forms;
+             DROP TABLE IF EXISTS {table};
+             CREATE TABLE {table} (id INTEGER PRIMARY KEY, {columns});
+             CREATE TABLE {table}_forms (form TEXT NOT NULL, row_id INTEGER NOT NULL REFERENCES {table}(id));
+             BEGIN;",
+            columns = columns.join(", "),
+        ))?;
+
+        let placeholders = vec!["?"; R::COLUMNS.len()].join(", ");
+        Ok(Self {
+            conn,
+            insert_row: format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                R::COLUMNS.join(", "),
+                placeholders
+            ),
+            insert_form: format!("INSERT INTO {}_forms (form, row_id) VALUES (?, ?)", table),
+            pending: 0,
+        })
+    }
+
+    fn insert<R: FormRow>(&mut self, row: &R) -> rusqlite::Result<()> {
+        self.conn
+            .prepare_cached(&self.insert_row)?
+            .execute(params_from_iter(row.values()))?;
+        let row_id = self.conn.last_insert_rowid();
+        let mut insert_form = self.conn.prepare_cached(&self.insert_form)?;
+        for form in row.forms() {
+            insert_form.execute((form, row_id))?;
+        }
+        drop(insert_form);
+
+        self.pending += 1;
+        if self.pending >= SQLITE_BATCH_SIZE {
+            self.conn.execute_batch("COMMIT; BEGIN;")?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    fn finish<R: FormRow>(self) -> rusqlite::Result<()> {
+        self.build_indexes::<R>()
+    }
+
+    /// Commits and builds the indexes, which is much faster than indexing while inserting.
+    fn build_indexes<R: FormRow>(&self) -> rusqlite::Result<()> {
+        let table = R::TABLE;
+        let lemma = R::LEMMA;
+        self.conn.execute_batch(&format!(
+            "COMMIT;
+             CREATE INDEX {table}_{lemma} ON {table}({lemma});
+             CREATE INDEX {table}_forms_form ON {table}_forms(form);
+             CREATE INDEX {table}_forms_row_id ON {table}_forms(row_id);
+             ANALYZE;"
+        ))
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_table {
+    use super::FormRow;
+    use arrow::array::{ArrayRef, StringBuilder};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+    use std::error::Error;
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    const BATCH_SIZE: usize = 65_536;
+
+    /// Writes all columns as strings, in row groups of `BATCH_SIZE`.
+    pub(crate) struct ParquetTable {
+        writer: ArrowWriter<File>,
+        schema: Arc<Schema>,
+        columns: Vec<StringBuilder>,
+        pending: usize,
+    }
+
+    impl ParquetTable {
+        pub(crate) fn new<R: FormRow>(path: &Path) -> Result<Self, Box<dyn Error>> {
+            let fields: Vec<_> = R::COLUMNS
+                .iter()
+                .map(|c| Field::new(*c, DataType::Utf8, false))
+                .collect();
+            let schema = Arc::new(Schema::new(fields));
+            let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), None)?;
+            Ok(Self {
+                writer,
+                schema,
+                columns: R::COLUMNS.iter().map(|_| StringBuilder::new()).collect(),
+                pending: 0,
+            })
+        }
+
+        pub(crate) fn push<R: FormRow>(&mut self, row: &R) -> Result<(), Box<dyn Error>> {
+            for (builder, value) in self.columns.iter_mut().zip(row.values()) {
+                builder.append_value(value);
+            }
+            self.pending += 1;
+            if self.pending >= BATCH_SIZE {
+                self.flush_batch()?;
+            }
+            Ok(())
+        }
+
+        fn flush_batch(&mut self) -> Result<(), Box<dyn Error>> {
+            let arrays: Vec<ArrayRef> = self
+                .columns
+                .iter_mut()
+                .map(|b| Arc::new(b.finish()) as ArrayRef)
+                .collect();
+            let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+            self.writer.write(&batch)?;
+            self.pending = 0;
+            Ok(())
+        }
+
+        pub(crate) fn finish(mut self) -> Result<(), Box<dyn Error>> {
+            if self.pending > 0 {
+                self.flush_batch()?;
+            }
+            self.writer.close()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[derive(Serialize)]
+    struct TestRow {
+        forms: String,
+        pratipadika: String,
+        linga: String,
+    }
+
+    impl FormRow for TestRow {
+        const TABLE: &'static str = "test_rows";
+        const COLUMNS: &'static [&'static str] = &["forms", "pratipadika", "linga"];
+        const LEMMA: &'static str = "pratipadika";
+
+        fn values(&self) -> Vec<String> {
+            vec![self.forms.clone(), self.pratipadika.clone(), self.linga.clone()]
+        }
+    }
+
+    fn rows() -> Vec<TestRow> {
+        [("रामः", "राम", "pum"), ("हरी|हरयः", "हरि", "pum")]
+            .into_iter()
+            .map(|(forms, pratipadika, linga)| TestRow {
+                forms: forms.to_string(),
+                pratipadika: pratipadika.to_string(),
+                linga: linga.to_string(),
+            })
+            .collect()
+    }
+
+    fn strings(conn: &Connection, sql: &str) -> Vec<String> {
+        let mut statement = conn.prepare(sql).unwrap();
+        let values = statement.query_map([], |row| row.get(0)).unwrap();
+        values.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn sqlite_tables_and_indexes() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut table = SqliteTable::with_connection::<TestRow>(conn).unwrap();
+        for row in rows() {
+            table.insert(&row).unwrap();
+        }
+        table.build_indexes::<TestRow>().unwrap();
+        let conn = &table.conn;
+
+        assert_eq!(
+            strings(conn, "SELECT name FROM pragma_table_info('test_rows')"),
+            vec!["id", "forms", "pratipadika", "linga"]
+        );
+        assert_eq!(
+            strings(conn, "SELECT name FROM pragma_table_info('test_rows_forms')"),
+            vec!["form", "row_id"]
+        );
+        assert_eq!(
+            strings(
+                conn,
+                "SELECT name FROM sqlite_master WHERE type = 'index' AND name NOT LIKE 'sqlite_%'
+                 ORDER BY name"
+            ),
+            vec!["test_rows_forms_form", "test_rows_forms_row_id", "test_rows_pratipadika"]
+        );
+        assert_eq!(
+            strings(
+                conn,
+                "SELECT f.form FROM test_rows_forms f JOIN test_rows t ON f.row_id = t.id
+                 WHERE t.pratipadika = 'हरि' ORDER BY f.rowid"
+            ),
+            vec!["हरी", "हरयः"]
+        );
+        assert_eq!(
+            strings(
+                conn,
+                "SELECT t.forms FROM test_rows t JOIN test_rows_forms f ON f.row_id = t.id
+                 WHERE f.form = 'हरयः'"
+            ),
+            vec!["हरी|हरयः"]
+        );
+    }
+
+    #[test]
+    fn csv_has_a_header_and_joined_forms() {
+        let path = std::env::temp_dir()
+            .join(format!("airaavata-form-table-{}.csv", std::process::id()));
+        let args = OutputArgs {
+            format: OutputFormat::Csv,
+            output: Some(path.clone()),
+        };
+        let mut writer = FormTableWriter::new::<TestRow>(&args).unwrap();
+        for row in rows() {
+            writer.write(&row).unwrap();
+        }
+        writer.finish::<TestRow>().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, "forms,pratipadika,linga\nरामः,राम,pum\nहरी|हरयः,हरि,pum\n");
+    }
+}
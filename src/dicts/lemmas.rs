@@ -0,0 +1,185 @@
+//! Lemmas that dictionaries are built from: every lemma of the kosha, or a list of our own, such as
+//! a textbook's vocabulary or a domain glossary.
+//!
+//! Lists are TSV files without a header. Lines starting with `#` are skipped, and text may be in
+//! any script.
+//!
+//! - prAtipadikas: `pratipadika<TAB>linga<TAB>meaning`, where `linga` is a comma-separated list
+//!   such as `pum,strI`.
+//! - dhAtus: `dhatu<TAB>gana<TAB>number<TAB>artha`, where `gana` is a vidyut name (`Bhvadi`) or a
+//!   number from 1 to 10, and `number` is the dhAtupATha number (`01.0001`).
+//!
+//! Meanings, numbers and arthas may be left empty or out. Meanings and arthas are shown as given,
+//! untransliterated.
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use vidyut_kosha::entries::PratipadikaEntry;
+use vidyut_kosha::Kosha;
+use vidyut_prakriya::args::{Dhatu, Gana, Linga, Pratipadika, Slp1String};
+
+use crate::util::{dev, parse_arg, to_slp, IterableEnum};
+
+pub(crate) struct PratipadikaLemma {
+    pub(crate) pratipadika: Pratipadika,
+    /// The prAtipadika in SLP1.
+    pub(crate) text: String,
+    pub(crate) lingas: Vec<Linga>,
+    /// Shown in entries as given. Empty for kosha lemmas.
+    pub(crate) meaning: String,
+}
+
+pub(crate) struct DhatuLemma {
+    pub(crate) dhatu: Dhatu,
+    /// dhAtupATha number, if given. Otherwise it's looked up in the dhAtupATha.
+    pub(crate) code: Option<String>,
+    /// Shown in entries as given. In Devanagari for kosha lemmas.
+    pub(crate) artha: String,
+}
+
+/// Name of the lemma source, for dictionary descriptions: the list's file name, or `kosha`.
+pub(crate) fn source_name(lemma_list: Option<&Path>) -> String {
+    match lemma_list.and_then(|x| x.file_stem()) {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => "kosha".to_string(),
+    }
+}
+
+/// Tab-separated fields of the non-empty, non-comment lines of `text`, trimmed.
+fn rows(text: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(i, line)| (i + 1, line.split('\t').map(str::trim).collect()))
+}
+
+fn parse_gana(s: &str) -> Result<Gana, String> {
+    match s.parse::<usize>() {
+        Ok(n) => Gana::all()
+            .into_iter()
+            .nth(n.wrapping_sub(1))
+            .ok_or_else(|| format!("Unknown gaNa {}", s)),
+        Err(_) => parse_arg(s),
+    }
+}
+
+pub(crate) fn parse_pratipadikas(text: &str) -> Result<Vec<PratipadikaLemma>, Box<dyn Error>> {
+    let mut lemmas = Vec::new();
+    for (line_num, fields) in rows(text) {
+        let (Some(pratipadika), Some(lingas)) = (fields.first(), fields.get(1)) else {
+            return Err(format!("Line {}: expected pratipadika<TAB>linga", line_num).into());
+        };
+        let text = to_slp(pratipadika);
+        let lingas = lingas
+            .split(',')
+            .map(|x| parse_arg::<Linga>(x.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("Line {}: {}", line_num, err))?;
+        lemmas.push(PratipadikaLemma {
+            pratipadika: Pratipadika::basic(Slp1String::from(&text)?),
+            text,
+            lingas,
+            meaning: fields.get(2).unwrap_or(&"").to_string(),
+        });
+    }
+    Ok(lemmas)
+}
+
+pub(crate) fn parse_dhatus(text: &str) -> Result<Vec<DhatuLemma>, Box<dyn Error>> {
+    let mut lemmas = Vec::new();
+    for (line_num, fields) in rows(text) {
+        let (Some(aupadeshika), Some(gana)) = (fields.first(), fields.get(1)) else {
+            return Err(format!("Line {}: expected dhatu<TAB>gana", line_num).into());
+        };
+        let gana = parse_gana(gana).map_err(|err| format!("Line {}: {}", line_num, err))?;
+        lemmas.push(DhatuLemma {
+            dhatu: Dhatu::mula(Slp1String::from(to_slp(aupadeshika))?, gana),
+            code: fields.get(2).filter(|x| !x.is_empty()).map(|x| x.to_string()),
+            artha: fields.get(3).unwrap_or(&"").to_string(),
+        });
+    }
+    Ok(lemmas)
+}
+
+pub(crate) fn read_pratipadikas(path: &Path) -> Result<Vec<PratipadikaLemma>, Box<dyn Error>> {
+    parse_pratipadikas(&fs::read_to_string(path)?)
+        .map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+pub(crate) fn read_dhatus(path: &Path) -> Result<Vec<DhatuLemma>, Box<dyn Error>> {
+    parse_dhatus(&fs::read_to_string(path)?)
+        .map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+/// The basic prAtipadikas of the kosha.
+pub(crate) fn kosha_pratipadikas(kosha: &Kosha) -> Vec<PratipadikaLemma> {
+    kosha
+        .pratipadikas()
+        .filter_map(|entry| match entry {
+            PratipadikaEntry::Basic(basic) => Some(PratipadikaLemma {
+                pratipadika: Pratipadika::from(basic.pratipadika()),
+                text: basic.pratipadika().text().to_string(),
+                lingas: basic.lingas().to_vec(),
+                meaning: String::new(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn kosha_dhatus(kosha: &Kosha) -> Vec<DhatuLemma> {
+    kosha
+        .dhatus()
+        .map(|entry| DhatuLemma {
+            dhatu: entry.dhatu().clone(),
+            code: None,
+            artha: dev(&entry.artha),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pratipadikas_with_meanings() {
+        let text = "# A textbook's vocabulary.\nrAma\tpum\tname of a king\n\nफल\tnapuMsaka\n";
+        let lemmas = parse_pratipadikas(text).unwrap();
+        assert_eq!(lemmas.len(), 2);
+        assert_eq!(lemmas[0].text, "rAma");
+        assert_eq!(lemmas[0].lingas, vec![Linga::Pum]);
+        assert_eq!(lemmas[0].meaning, "name of a king");
+        assert_eq!(lemmas[1].text, "Pala");
+        assert_eq!(lemmas[1].meaning, "");
+    }
+
+    #[test]
+    fn indented_comments_are_skipped() {
+        let lemmas = parse_pratipadikas("  # vocabulary\n\t# lesson 1\nrAma\tpum\n").unwrap();
+        assert_eq!(lemmas.len(), 1);
+        assert_eq!(lemmas[0].text, "rAma");
+    }
+
+    #[test]
+    fn pratipadikas_need_a_linga() {
+        assert!(parse_pratipadikas("rAma\n").is_err());
+        assert!(parse_pratipadikas("rAma\tpuMs\n").is_err());
+    }
+
+    #[test]
+    fn dhatus_by_gana_name_or_number() {
+        let text = "BU\tBhvadi\t01.0001\tsattAyAm\nएधँ\t1\n";
+        let lemmas = parse_dhatus(text).unwrap();
+        assert_eq!(lemmas[0].dhatu.gana(), Some(Gana::Bhvadi));
+        assert_eq!(lemmas[0].code.as_deref(), Some("01.0001"));
+        assert_eq!(lemmas[0].artha, "sattAyAm");
+        assert_eq!(lemmas[1].dhatu.aupadeshika(), Some("eDa~"));
+        assert_eq!(lemmas[1].dhatu.gana(), Some(Gana::Bhvadi));
+        assert_eq!(lemmas[1].code, None);
+        assert!(parse_dhatus("BU\t11\n").is_err());
+    }
+}
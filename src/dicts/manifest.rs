@@ -0,0 +1,128 @@
+//! A record, kept next to the generated dictionaries, of what each one was built from.
+//!
+//! A dictionary is rebuilt only if its file is missing or if any of its inputs (data file hashes,
+//! vidyut data version, vidyut-prakriya version, airaavata version, or the config used for it)
+//! changed. A record is saved as soon as each dictionary or volume is complete, and volumes are
+//! built one at a time, so rerunning an interrupted build skips the volumes it completed.
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "build-manifest.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DictInputs {
+    /// sha256 of each input data file, keyed by its path relative to the data directory.
+    pub(crate) data_hashes: BTreeMap<String, String>,
+    pub(crate) data_version: String,
+    /// Version of the vidyut-prakriya crate that derived the forms. Empty in records made before it
+    /// was recorded, so that those dictionaries are rebuilt.
+    #[serde(default)]
+    pub(crate) vidyut_version: String,
+    pub(crate) airaavata_version: String,
+    /// Whatever settings select the entries of this dictionary.
+    pub(crate) config: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct DictRecord {
+    pub(crate) inputs: DictInputs,
+    pub(crate) num_entries: usize,
+    pub(crate) completed_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BuildManifest {
+    dicts: BTreeMap<String, DictRecord>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl BuildManifest {
+    /// Loads the manifest in `dest_dir`, or starts an empty one.
+    pub(crate) fn load(dest_dir: &Path) -> Self {
+        let path = dest_dir.join(MANIFEST_FILE);
+        let mut manifest: Self = fs::read_to_string(&path)
+            .ok()
+            .and_then(|x| serde_json::from_str(&x).ok())
+            .unwrap_or_default();
+        manifest.path = path;
+        manifest
+    }
+
+    /// Whether `dict_name` was completed at `dest_path` with exactly these inputs.
+    pub(crate) fn is_fresh(&self, dict_name: &str, dest_path: &Path, inputs: &DictInputs) -> bool {
+        dest_path.exists()
+            && self
+                .dicts
+                .get(dict_name)
+                .map_or(false, |record| &record.inputs == inputs)
+    }
+
+    /// Records a completed dictionary and saves the manifest right away.
+    pub(crate) fn record(
+        &mut self,
+        dict_name: &str,
+        inputs: DictInputs,
+        num_entries: usize,
+    ) -> io::Result<()> {
+        self.dicts.insert(
+            dict_name.to_string(),
+            DictRecord {
+                inputs,
+                num_entries,
+                completed_at: chrono::Local::now().to_rfc3339(),
+            },
+        );
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        // Write and rename, so that an interruption never leaves a truncated manifest.
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+/// Hashes each of `rel_paths` under `data_path`. A directory (like `kosha/`) is hashed over all its
+/// files, in sorted order. Missing paths are skipped.
+pub(crate) fn hash_data_files(data_path: &Path, rel_paths: &[&str]) -> BTreeMap<String, String> {
+    let mut hashes = BTreeMap::new();
+    for rel_path in rel_paths {
+        let path = data_path.join(rel_path);
+        let mut files = Vec::new();
+        collect_files(&path, &mut files);
+        if files.is_empty() {
+            continue;
+        }
+        files.sort();
+
+        let mut hasher = Sha256::new();
+        for file in files {
+            hasher.update(file.strip_prefix(&path).unwrap_or(&file).to_string_lossy().as_bytes());
+            if let Ok(mut f) = File::open(&file) {
+                io::copy(&mut f, &mut hasher).ok();
+            }
+        }
+        hashes.insert(rel_path.to_string(), format!("{:x}", hasher.finalize()));
+    }
+    hashes
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+    } else if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            collect_files(&entry.path(), files);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Chains of sanAdi pratyayas, and the dictionary names derived from them.
+//!
+//! A chain is written as sanAdi names joined by `-`, e.g. `Nic-san`. The empty chain (no sanAdi)
+//! is written as `none`.
+use std::fmt;
+use std::str::FromStr;
+use vidyut_prakriya::args::{Dhatu, Pratipadika, Sanadi};
+
+/// sanAdi pratyayas which make a nAmadhAtu from a subanta, rather than extending a dhAtu.
+const NAAMA_SANAADI: [Sanadi; 4] = [Sanadi::kyac, Sanadi::kAmyac, Sanadi::kyaN, Sanadi::kyaz];
+
+/// sanAdi pratyayas which can only be added directly to a mUla-dhAtu.
+const MULA_ONLY_SANAADI: [Sanadi; 2] = [Sanadi::yaN, Sanadi::yaNluk];
+
+const MAX_CHAIN_LEN: usize = 3;
+
+/// What a chain is attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SanaadiBase {
+    /// A dhAtu from the dhAtupATha.
+    Dhatu,
+    /// A prAtipadika, which first becomes a nAmadhAtu.
+    Pratipadika,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct SanaadiChain(Vec<Sanadi>);
+
+impl SanaadiChain {
+    pub(crate) fn new(sanaadis: Vec<Sanadi>, base: SanaadiBase) -> Result<Self, String> {
+        let chain = Self(sanaadis);
+        chain.validate(base)?;
+        Ok(chain)
+    }
+
+    /// The chains for which we have historically published dictionaries.
+    pub(crate) fn defaults() -> Vec<Self> {
+        [
+            vec![],
+            vec![Sanadi::Ric],
+            vec![Sanadi::san],
+            vec![Sanadi::yaN],
+            vec![Sanadi::yaNluk],
+            vec![Sanadi::san, Sanadi::Ric],
+            vec![Sanadi::Ric, Sanadi::san],
+        ]
+        .into_iter()
+        .map(Self)
+        .collect()
+    }
+
+    /// The chains used for nAmadhAtu dictionaries.
+    pub(crate) fn naama_defaults() -> Vec<Self> {
+        [vec![Sanadi::Ric], vec![Sanadi::kyac], vec![Sanadi::kyaN]]
+            .into_iter()
+            .map(Self)
+            .collect()
+    }
+
+    /// Parses a comma-separated list of chains, such as `none,Nic,san,Nic-san`.
+    pub(crate) fn parse_list(spec: &str, base: SanaadiBase) -> Result<Vec<Self>, String> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse::<Self>().and_then(|c| c.validate(base).map(|_| c)))
+            .collect()
+    }
+
+    pub(crate) fn sanaadis(&self) -> &[Sanadi] {
+        &self.0
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Makes a nAmadhAtu from `pratipadika` with the first sanAdi, and adds the rest to it.
+    ///
+    /// Only meaningful for chains valid over `SanaadiBase::Pratipadika`.
+    pub(crate) fn naamadhaatu(&self, pratipadika: Pratipadika) -> Dhatu {
+        let (first, rest) = self.0.split_first().expect("nAmadhAtu chains are never empty");
+        Dhatu::nama(pratipadika, Some(*first)).with_sanadi(rest)
+    }
+
+    /// Name of the dictionary holding forms of this chain, e.g. `vidyut-Nic-san-tiN`.
+    pub(crate) fn dict_name(&self, suffix: &str) -> String {
+        if self.is_empty() {
+            format!("vidyut-{}", suffix)
+        } else {
+            format!("vidyut-{}-{}", self, suffix)
+        }
+    }
+
+    /// Rejects chains which can't produce any form.
+    pub(crate) fn validate(&self, base: SanaadiBase) -> Result<(), String> {
+        if self.0.len() > MAX_CHAIN_LEN {
+            return Err(format!("{}: at most {} sanAdis are supported", self, MAX_CHAIN_LEN));
+        }
+        for (i, sanaadi) in self.0.iter().enumerate() {
+            if self.0[..i].contains(sanaadi) {
+                return Err(format!("{}: {} is repeated", self, slug(*sanaadi)));
+            }
+            if NAAMA_SANAADI.contains(sanaadi) {
+                if base == SanaadiBase::Dhatu {
+                    return Err(format!("{}: {} applies only to prAtipadikas", self, slug(*sanaadi)));
+                }
+                if i > 0 {
+                    return Err(format!("{}: {} must come first", self, slug(*sanaadi)));
+                }
+            }
+            if MULA_ONLY_SANAADI.contains(sanaadi) && (i > 0 || base == SanaadiBase::Pratipadika) {
+                return Err(format!("{}: {} applies only to a mUla-dhAtu", self, slug(*sanaadi)));
+            }
+        }
+        if base == SanaadiBase::Pratipadika
+            && !self.0.first().map_or(false, |x| NAAMA_SANAADI.contains(x) || *x == Sanadi::Ric)
+        {
+            return Err(format!("{}: a nAmadhAtu needs Nic, kyac, kAmyac, kyaN or kyaz first", self));
+        }
+        Ok(())
+    }
+}
+
+/// Stable ASCII name for a sanAdi, without hyphens so that chains can be split on `-`.
+fn slug(sanaadi: Sanadi) -> String {
+    match sanaadi {
+        Sanadi::Ric => "Nic".to_string(),
+        _ => sanaadi.as_str().replace('-', ""),
+    }
+}
+
+impl fmt::Display for SanaadiChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+        let names: Vec<_> = self.0.iter().map(|x| slug(*x)).collect();
+        write!(f, "{}", names.join("-"))
+    }
+}
+
+impl FromStr for SanaadiChain {
+    type Err = String;
+
+    /// Parses a chain without validating it against a base.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() || s == "none" {
+            return Ok(Self(vec![]));
+        }
+        // "yaN-luk" is a single sanAdi, so glue it back before splitting.
+        let s = s.replace("yaN-luk", "yaNluk");
+        let sanaadis = s
+            .split('-')
+            .map(|name| {
+                Sanadi::iter()
+                    .find(|x| slug(*x) == name || x.as_str() == name)
+                    .ok_or_else(|| format!("Unknown sanAdi {} in {}", name, s))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(sanaadis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(sanaadis: &[Sanadi]) -> SanaadiChain {
+        SanaadiChain(sanaadis.to_vec())
+    }
+
+    #[test]
+    fn parse_list_of_chains() {
+        let chains =
+            SanaadiChain::parse_list("none, Nic,san-Nic,yaN-luk,", SanaadiBase::Dhatu).unwrap();
+        assert_eq!(
+            chains,
+            vec![
+                chain(&[]),
+                chain(&[Sanadi::Ric]),
+                chain(&[Sanadi::san, Sanadi::Ric]),
+                chain(&[Sanadi::yaNluk]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_list_rejects_bad_tokens() {
+        assert!(SanaadiChain::parse_list("Nic,foo", SanaadiBase::Dhatu).is_err());
+        assert!(SanaadiChain::parse_list("Nic--san", SanaadiBase::Dhatu).is_err());
+        assert!(SanaadiChain::parse_list("Nic,kyac", SanaadiBase::Dhatu).is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for c in SanaadiChain::defaults() {
+            assert_eq!(c.to_string().parse::<SanaadiChain>().unwrap(), c);
+        }
+    }
+
+    #[test]
+    fn validate_chain_length() {
+        let long = chain(&[Sanadi::kyac, Sanadi::san, Sanadi::Ric, Sanadi::kyaN]);
+        assert!(long.sanaadis().len() > MAX_CHAIN_LEN);
+        let err = long.validate(SanaadiBase::Pratipadika).unwrap_err();
+        assert!(err.contains("at most"), "{}", err);
+        let longest = chain(&[Sanadi::kyac, Sanadi::san, Sanadi::Ric]);
+        assert_eq!(longest.sanaadis().len(), MAX_CHAIN_LEN);
+        assert_eq!(longest.validate(SanaadiBase::Pratipadika), Ok(()));
+    }
+
+    #[test]
+    fn validate_chain_repeats() {
+        let repeated = chain(&[Sanadi::san, Sanadi::Ric, Sanadi::san]);
+        assert!(repeated.sanaadis().len() <= MAX_CHAIN_LEN);
+        let err = repeated.validate(SanaadiBase::Dhatu).unwrap_err();
+        assert!(err.contains("repeated"), "{}", err);
+        assert!(chain(&[Sanadi::Ric, Sanadi::san]).validate(SanaadiBase::Dhatu).is_ok());
+    }
+
+    #[test]
+    fn validate_mula_only() {
+        for sanaadi in MULA_ONLY_SANAADI {
+            assert!(chain(&[sanaadi]).validate(SanaadiBase::Dhatu).is_ok());
+            assert!(chain(&[Sanadi::Ric, sanaadi]).validate(SanaadiBase::Dhatu).is_err());
+        }
+    }
+
+    #[test]
+    fn naamadhaatu_chains() {
+        for sanaadi in NAAMA_SANAADI {
+            assert_eq!(chain(&[sanaadi]).validate(SanaadiBase::Pratipadika), Ok(()));
+            assert!(chain(&[sanaadi]).validate(SanaadiBase::Dhatu).is_err());
+            assert!(chain(&[Sanadi::Ric, sanaadi]).validate(SanaadiBase::Pratipadika).is_err());
+        }
+        assert_eq!(chain(&[Sanadi::kyac, Sanadi::san]).validate(SanaadiBase::Pratipadika), Ok(()));
+        for sanaadi in MULA_ONLY_SANAADI {
+            assert!(chain(&[sanaadi]).validate(SanaadiBase::Pratipadika).is_err());
+            assert!(chain(&[Sanadi::kyac, sanaadi]).validate(SanaadiBase::Pratipadika).is_err());
+        }
+        assert!(chain(&[]).validate(SanaadiBase::Pratipadika).is_err());
+        assert!(SanaadiChain::parse_list("kyac,kAmyac,kyaN,kyaz", SanaadiBase::Pratipadika).is_ok());
+        assert!(SanaadiChain::parse_list("kyac,yaN-luk", SanaadiBase::Pratipadika).is_err());
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        for c in SanaadiChain::defaults() {
+            assert_eq!(c.validate(SanaadiBase::Dhatu), Ok(()));
+        }
+        for c in SanaadiChain::naama_defaults() {
+            assert_eq!(c.validate(SanaadiBase::Pratipadika), Ok(()));
+        }
+    }
+
+    #[test]
+    fn dict_names() {
+        assert_eq!(chain(&[]).dict_name("kRdanta"), "vidyut-kRdanta");
+        assert_eq!(chain(&[Sanadi::san, Sanadi::Ric]).dict_name("tiN"), "vidyut-san-Nic-tiN");
+        assert_eq!(chain(&[Sanadi::yaNluk]).dict_name("kRdanta"), "vidyut-yaNluk-kRdanta");
+        assert_eq!(chain(&[Sanadi::yaNluk]).dict_name("tiN"), "vidyut-yaNluk-tiN");
+    }
+}
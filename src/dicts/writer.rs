@@ -0,0 +1,141 @@
+//! Writers to which dictionary entries are pushed as soon as they are generated, so that a whole
+//! dictionary never has to be held in memory.
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Definition {
+    pub(crate) headwords: Vec<String>,
+    pub(crate) meaning: String,
+}
+
+impl Definition {
+    /// The entry as written to a Babylon file: headwords joined by `|`, then the meaning.
+    pub(crate) fn to_babylon(&self) -> String {
+        format!("{}\n{}\n\n", self.headwords.join("|"), self.meaning)
+    }
+}
+
+/// Descriptive fields written to the head of a dictionary file, set per dictionary family.
+#[derive(Clone, Debug)]
+pub(crate) struct DictMetadata {
+    pub(crate) bookname: String,
+    pub(crate) description: String,
+    pub(crate) author: String,
+    pub(crate) license: String,
+    /// Version of the vidyut data the forms were generated from.
+    pub(crate) data_version: String,
+}
+
+impl DictMetadata {
+    pub(crate) fn new(
+        bookname: impl Into<String>,
+        description: impl Into<String>,
+        data_version: impl Into<String>,
+    ) -> Self {
+        Self {
+            bookname: bookname.into(),
+            description: description.into(),
+            author: "sanskrit-coders (airaavata, vidyut)".to_string(),
+            license: "CC-BY-SA 4.0".to_string(),
+            data_version: data_version.into(),
+        }
+    }
+}
+
+/// Receives dictionary entries as they are generated.
+///
+/// Entries may arrive in any order. Output formats that need sorted entries must sort when
+/// finishing; Babylon doesn't, since stardict converters sort on their own.
+pub(crate) trait DictWriter {
+    fn push(&mut self, definition: Definition) -> io::Result<()>;
+
+    fn push_all(&mut self, definitions: Vec<Definition>) -> io::Result<()> {
+        for definition in definitions {
+            self.push(definition)?;
+        }
+        Ok(())
+    }
+
+    /// Completes the output and returns the number of entries written.
+    fn finish(self: Box<Self>) -> io::Result<usize>;
+}
+
+/// Streams entries into `<dest>.part`, and on finishing writes the header (which needs the final
+/// counts) followed by the entries into `<dest>`.
+pub(crate) struct BabylonWriter {
+    dest_path: PathBuf,
+    part_path: PathBuf,
+    body: BufWriter<File>,
+    metadata: DictMetadata,
+    num_entries: usize,
+    num_headwords: usize,
+}
+
+impl BabylonWriter {
+    pub(crate) fn new(dest_path: &Path, metadata: DictMetadata) -> io::Result<Self> {
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut part_path = dest_path.as_os_str().to_owned();
+        part_path.push(".part");
+        let part_path = PathBuf::from(part_path);
+        let body = BufWriter::new(File::create(&part_path)?);
+        Ok(Self {
+            dest_path: dest_path.to_path_buf(),
+            part_path,
+            body,
+            metadata,
+            num_entries: 0,
+            num_headwords: 0,
+        })
+    }
+
+    /// Header lines read by stardict/pyglossary Babylon converters. Every value must fit on one
+    /// line.
+    fn header(&self) -> String {
+        let description = format!(
+            "{}<BR>{} entries, {} headwords. Generated on {} by airaavata {} from vidyut data {}. License: {}.",
+            self.metadata.description,
+            self.num_entries,
+            self.num_headwords,
+            chrono::Local::now().format("%Y-%m-%d"),
+            env!("CARGO_PKG_VERSION"),
+            self.metadata.data_version,
+            self.metadata.license,
+        );
+        let fields = [
+            ("stripmethod", "keep".to_string()),
+            ("sametypesequence", "h".to_string()),
+            ("bookname", self.metadata.bookname.clone()),
+            ("author", self.metadata.author.clone()),
+            ("description", description),
+        ];
+        let mut header = String::from("\n");
+        for (key, value) in fields {
+            header.push_str(&format!("#{}={}\n", key, value.replace('\n', " ")));
+        }
+        header.push('\n');
+        header
+    }
+}
+
+impl DictWriter for BabylonWriter {
+    fn push(&mut self, definition: Definition) -> io::Result<()> {
+        self.num_entries += 1;
+        self.num_headwords += definition.headwords.len();
+        self.body.write_all(definition.to_babylon().as_bytes())
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<usize> {
+        self.body.flush()?;
+        let mut out = BufWriter::new(File::create(&self.dest_path)?);
+        out.write_all(self.header().as_bytes())?;
+        io::copy(&mut File::open(&self.part_path)?, &mut out)?;
+        out.flush()?;
+        fs::remove_file(&self.part_path)?;
+        Ok(self.num_entries)
+    }
+}
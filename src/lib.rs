@@ -0,0 +1,33 @@
+//! Sanskrit dictionaries, prakriyA pages and analysis tools built on vidyut.
+//!
+//! The `airaavata` binary (`main.rs`) runs `cli::run`. Built with the `python` feature, as
+//! `setup.py` does, this crate is also the `airaavata._core` Python extension module.
+mod dicts {
+    pub(crate) mod create_all_krdantas;
+    pub(crate) mod create_all_subantas;
+    pub(crate) mod create_all_tinantas;
+    pub(crate) mod form_table;
+    pub(crate) mod babylon;
+    pub(crate) mod babylon_entries;
+    pub(crate) mod lemmas;
+    pub(crate) mod manifest;
+    pub(crate) mod sanaadi;
+    pub(crate) mod writer;
+}
+mod analysis;
+mod chandas;
+mod cheda;
+mod compare;
+mod dhaatu;
+mod pada;
+mod pages;
+mod paradigms;
+mod prakriyaa;
+mod repl;
+mod serve;
+mod steps;
+mod util;
+
+pub mod cli;
+#[cfg(feature = "python")]
+mod python;
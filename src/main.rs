@@ -1,10 +1,11 @@
-mod dicts {
-    mod create_all_tinantas;
-    mod babylon;
-}
-mod util;
-mod prakriyaa;
+use airaavata::cli;
+use env_logger::Env;
 
 fn main() {
-    println!("Hello, world!");
+    // Progress and summaries are logged at info, so show them unless RUST_LOG says otherwise.
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    if let Err(err) = cli::run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
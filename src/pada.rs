@@ -0,0 +1,168 @@
+//! Padas and paradigms specified by `key=value` parameters, such as
+//! `dhatu=BU gana=Bhvadi lakara=Lat purusha=Prathama vacana=Eka` or
+//! `pratipadika=rAma linga=Pum vibhakti=Prathama vacana=Eka`.
+//!
+//! dhAtus and prAtipadikas may be in any script. It's detected unless named by `input_scheme`
+//! (such as `input_scheme=Slp1`), which is safer for ASCII text. Categories take vidyut's names, as
+//! in `util::parse_arg`. A dhAtu may also take `sanadi` (a chain such as `Nic-san`), and a tinanta
+//! `prayoga` (default `Kartari`) and `pada`.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use vidyut_lipi::Scheme;
+use vidyut_prakriya::args::{
+    Dhatu, Linga, Pratipadika, Prayoga, Purusha, Slp1String, Subanta, Tinanta, Vacana, Vibhakti,
+};
+use vidyut_prakriya::{Prakriya, Vyakarana};
+
+use crate::dicts::sanaadi::{SanaadiBase, SanaadiChain};
+use crate::util::{parse_arg, parse_scheme, to_scheme, to_slp_from, IterableEnum};
+
+pub(crate) type Params = HashMap<String, String>;
+
+/// Parses `key=value` words, as typed in the REPL.
+pub(crate) fn parse_params(text: &str) -> Params {
+    text.split_whitespace()
+        .filter_map(|x| x.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn get<'a>(params: &'a Params, key: &str) -> Result<&'a str, String> {
+    params
+        .get(key)
+        .map(String::as_str)
+        .ok_or_else(|| format!("Missing {}", key))
+}
+
+fn get_arg<T: IterableEnum + Display + Debug>(params: &Params, key: &str) -> Result<T, String> {
+    parse_arg(get(params, key)?)
+}
+
+fn get_opt_arg<T: IterableEnum + Display + Debug>(
+    params: &Params,
+    key: &str,
+) -> Result<Option<T>, String> {
+    params.get(key).map(|x| parse_arg(x)).transpose()
+}
+
+/// The value of `key` in SLP1, read from `input_scheme` if that's given.
+fn slp1_param(params: &Params, key: &str) -> Result<Slp1String, String> {
+    let input_scheme = params.get("input_scheme").map(|x| parse_scheme(x)).transpose()?;
+    Slp1String::from(to_slp_from(get(params, key)?, input_scheme)).map_err(|e| e.to_string())
+}
+
+fn dhatu(params: &Params) -> Result<Dhatu, String> {
+    let dhatu = Dhatu::mula(slp1_param(params, "dhatu")?, get_arg(params, "gana")?);
+    Ok(match params.get("sanadi") {
+        Some(spec) => {
+            let chain: SanaadiChain = spec.parse()?;
+            chain.validate(SanaadiBase::Dhatu)?;
+            dhatu.with_sanadi(chain.sanaadis())
+        }
+        None => dhatu,
+    })
+}
+
+fn pratipadika(params: &Params) -> Result<Pratipadika, String> {
+    Ok(Pratipadika::basic(slp1_param(params, "pratipadika")?))
+}
+
+fn tinanta(params: &Params, purusha: Purusha, vacana: Vacana) -> Result<Tinanta, String> {
+    let mut builder = Tinanta::builder();
+    builder
+        .dhatu(dhatu(params)?)
+        .prayoga(get_opt_arg(params, "prayoga")?.unwrap_or(Prayoga::Kartari))
+        .lakara(get_arg(params, "lakara")?)
+        .purusha(purusha)
+        .vacana(vacana);
+    if let Some(pada) = get_opt_arg(params, "pada")? {
+        builder.pada(pada);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn subanta(params: &Params, vibhakti: Vibhakti, vacana: Vacana) -> Result<Subanta, String> {
+    Subanta::builder()
+        .pratipadika(pratipadika(params)?)
+        .linga(get_arg::<Linga>(params, "linga")?)
+        .vibhakti(vibhakti)
+        .vacana(vacana)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// A fully specified pada.
+pub(crate) enum PadaSpec {
+    Tinanta(Tinanta),
+    Subanta(Subanta),
+}
+
+impl PadaSpec {
+    /// A tinanta if `dhatu` is given, else a subanta.
+    pub(crate) fn from_params(params: &Params) -> Result<Self, String> {
+        if params.contains_key("dhatu") {
+            let (purusha, vacana) = (get_arg(params, "purusha")?, get_arg(params, "vacana")?);
+            Ok(Self::Tinanta(tinanta(params, purusha, vacana)?))
+        } else if params.contains_key("pratipadika") {
+            let (vibhakti, vacana) = (get_arg(params, "vibhakti")?, get_arg(params, "vacana")?);
+            Ok(Self::Subanta(subanta(params, vibhakti, vacana)?))
+        } else {
+            Err("Give a dhatu or a pratipadika".to_string())
+        }
+    }
+
+    pub(crate) fn derive(&self, v: &Vyakarana) -> Vec<Prakriya> {
+        match self {
+            Self::Tinanta(t) => v.derive_tinantas(t),
+            Self::Subanta(s) => v.derive_subantas(s),
+        }
+    }
+}
+
+/// One cell of a paradigm table: a purusha (for tinantas) or vibhakti (for subantas), and a
+/// vacana.
+#[derive(Debug, Serialize)]
+pub(crate) struct ParadigmCell {
+    pub(crate) row: String,
+    pub(crate) vacana: String,
+    pub(crate) forms: Vec<String>,
+}
+
+/// The 3x3 table of a dhAtu in one lakAra, or the 8x3 table of a prAtipadika in one liNga. Forms
+/// are sorted and in `scheme`.
+pub(crate) fn paradigm(
+    v: &Vyakarana,
+    params: &Params,
+    scheme: Scheme,
+) -> Result<Vec<ParadigmCell>, String> {
+    let cell = |row: String, vacana: Vacana, prakriyas: Vec<Prakriya>| {
+        let mut forms: Vec<_> = prakriyas.iter().map(|p| to_scheme(p.text(), scheme)).collect();
+        forms.sort();
+        ParadigmCell {
+            row,
+            vacana: format!("{:?}", vacana),
+            forms,
+        }
+    };
+
+    let mut cells = Vec::new();
+    if params.contains_key("dhatu") {
+        for purusha in Purusha::iter() {
+            for vacana in Vacana::iter() {
+                let prakriyas = v.derive_tinantas(&tinanta(params, purusha, vacana)?);
+                cells.push(cell(format!("{:?}", purusha), vacana, prakriyas));
+            }
+        }
+    } else if params.contains_key("pratipadika") {
+        for vibhakti in Vibhakti::iter() {
+            for vacana in Vacana::iter() {
+                let prakriyas = v.derive_subantas(&subanta(params, vibhakti, vacana)?);
+                cells.push(cell(format!("{:?}", vibhakti), vacana, prakriyas));
+            }
+        }
+    } else {
+        return Err("Give a dhatu or a pratipadika".to_string());
+    }
+    Ok(cells)
+}
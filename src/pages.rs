@@ -0,0 +1,468 @@
+//! Writes prakriyA pages for the vishvAsa site: one Markdown page per derived form, and an
+//! `_index.md` per lemma linking its forms by paradigm cell. Each form page links back to its
+//! lemma and to the other forms of its paradigm.
+//!
+//! tinantas and kRdantas go under `--dhatu-dir` (in `tiNantAni/` and `kRdantAni/`), subantas
+//! under `--sup-dir`, as `airaavata/prakriyaa.py` does. File names are derived from the text
+//! alone, as `prakriyaa.py` names them, and files are only rewritten when their content changes,
+//! so rebuilding gives a clean diff. A dhAtu's directory is named by its aupadeshika and gaNa, and
+//! also by its dhAtupATha number if the root is listed more than once in its gaNa.
+//!
+//! dhAtus come from the dhAtupATha and prAtipadikas from the kosha, which is only loaded if
+//! prAtipadika pages are asked for.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- pages --dhatus गमॢँ,भू --pratipadikas राम
+//!     cargo run --release -- pages --all-dhatus
+use indicatif::ProgressBar;
+use log::{error, info};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use vidyut_kosha::entries::PratipadikaEntry;
+use vidyut_lipi::{transliterate, Mapping, Scheme};
+use vidyut_prakriya::args::{
+    BaseKrt, Dhatu, DhatuPada, Krdanta, Lakara, Linga, Pratipadika, Prayoga, Purusha, Subanta,
+    Tinanta, Vacana, Vibhakti,
+};
+use vidyut_prakriya::Prakriya;
+
+use crate::dhaatu::gana_name;
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::steps::StepOptions;
+use crate::util::{dev, to_slp};
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    /// Root of the dhAtu prakriyA pages.
+    #[arg(long, env = "PRAKRIYA_DHATU")]
+    pub(crate) dhatu_dir: PathBuf,
+
+    /// Root of the subanta prakriyA pages.
+    #[arg(long, env = "PRAKRIYA_SUP")]
+    pub(crate) sup_dir: PathBuf,
+
+    /// Comma-separated aupadeshika dhAtus, in any script.
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) dhatus: Vec<String>,
+
+    /// Comma-separated prAtipadikas, in any script.
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) pratipadikas: Vec<String>,
+
+    /// Use every dhAtu in the dhAtupATha.
+    #[arg(long)]
+    pub(crate) all_dhatus: bool,
+
+    /// Use every basic prAtipadika in the kosha.
+    #[arg(long)]
+    pub(crate) all_pratipadikas: bool,
+
+    #[command(flatten)]
+    pub(crate) steps: StepOptions,
+}
+
+/// Longest file name that `storage_name` gives, without the extension.
+const STORAGE_NAME_MAX_LEN: usize = 50;
+
+/// Stable, ASCII file name for SLP1 text, as `file_helper.get_storage_name` of curation_utils gives
+/// for the same text, so that pages keep the names `prakriyaa.py` gave them: `/` becomes `__`, the
+/// text is written in optitrans, characters other than ASCII letters, digits and `_-~./` are
+/// dropped, spaces become `_`, and the name is cut at 50 characters.
+pub(crate) fn storage_name(text: &str) -> String {
+    let text = Regex::new("/ *").unwrap().replace_all(text, "__");
+    let optitrans = optitrans(&transliterate(&text, &Mapping::new(Scheme::Slp1, Scheme::Itrans)));
+    let cleaned: String = optitrans
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || " _-~./".contains(*c))
+        .collect();
+    let name = Regex::new(" +").unwrap().replace_all(cleaned.trim(), "_");
+    name.chars().take(STORAGE_NAME_MAX_LEN).collect()
+}
+
+/// optitrans differs from ITRANS in writing the vocalic R and L as `R^i` and `L^i`, and ~N and ~n
+/// before a consonant of their own varga as `n`.
+fn optitrans(itrans: &str) -> String {
+    let text = itrans
+        .replace("RRi", "R^i")
+        .replace("RRI", "R^I")
+        .replace("LLi", "L^i")
+        .replace("LLI", "L^I");
+    let text = Regex::new("~N([kg])").unwrap().replace_all(&text, "n$1");
+    Regex::new("~n([cCj])").unwrap().replace_all(&text, "n$1").to_string()
+}
+
+fn link(form: &str) -> String {
+    format!("[{}]({}.md)", dev(form), storage_name(form))
+}
+
+/// Writes `content` unless the file already has it. Returns whether the file was written.
+fn write_if_changed(path: &Path, content: &str) -> io::Result<bool> {
+    if fs::read_to_string(path).is_ok_and(|old| old == content) {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(true)
+}
+
+fn front_matter(title: &str) -> String {
+    format!("+++\ntitle = \"{}\"\n+++\n", title)
+}
+
+/// The pages of one lemma.
+struct LemmaPages {
+    title: String,
+    dir: PathBuf,
+    /// Paradigm cells (e.g. `लट् कर्तरि`) with their forms (SLP1), in derivation order.
+    groups: Vec<(String, Vec<String>)>,
+    /// Sections of each form's page, keyed by form (SLP1).
+    pages: BTreeMap<String, Vec<String>>,
+}
+
+impl LemmaPages {
+    fn new(title: String, dir: PathBuf) -> Self {
+        Self {
+            title,
+            dir,
+            groups: Vec::new(),
+            pages: BTreeMap::new(),
+        }
+    }
+
+    fn start_group(&mut self, heading: String) {
+        self.groups.push((heading, Vec::new()));
+    }
+
+    /// Adds `prakriyas` to the current group, under `heading` on each form's page.
+    fn add(&mut self, builder: &PageBuilder, heading: &str, prakriyas: &[Prakriya]) {
+        let (_, group) = self.groups.last_mut().expect("a group was started");
+        for p in prakriyas {
+            let form = p.text();
+            let section = format!(
+                "## {}\n\n{}",
+                heading,
+                builder
+                    .helper
+                    .render_prakriya(p, &builder.steps)
+                    .replacen("## ", "### ", 1)
+            );
+            self.pages.entry(form.clone()).or_default().push(section);
+            if !group.contains(&form) {
+                group.push(form);
+            }
+        }
+    }
+
+    fn related_forms(&self, form: &str) -> Vec<&String> {
+        let mut related: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|(_, forms)| forms.iter().any(|x| x == form))
+            .flat_map(|(_, forms)| forms)
+            .filter(|x| *x != form)
+            .collect();
+        related.sort();
+        related.dedup();
+        related
+    }
+
+    /// Writes the form pages and the index. Returns the number of files written.
+    fn write(&self) -> io::Result<usize> {
+        if self.pages.is_empty() {
+            return Ok(0);
+        }
+        let mut num_written = 0;
+        for (form, sections) in &self.pages {
+            let mut content = front_matter(&dev(form));
+            content.push_str(&format!("\n[{}](_index.md)\n\n", self.title));
+            content.push_str(&sections.join("\n\n"));
+            let related = self.related_forms(form);
+            if !related.is_empty() {
+                let links: Vec<_> = related.iter().map(|x| link(x)).collect();
+                content.push_str(&format!("\n## सम्बद्धरूपाणि\n{}\n", links.join(" · ")));
+            }
+            let path = self.dir.join(format!("{}.md", storage_name(form)));
+            num_written += write_if_changed(&path, &content)? as usize;
+        }
+
+        let mut index = front_matter(&self.title);
+        for (heading, forms) in self.groups.iter().filter(|(_, forms)| !forms.is_empty()) {
+            let links: Vec<_> = forms.iter().map(|x| link(x)).collect();
+            index.push_str(&format!("\n## {}\n{}\n", heading, links.join(" · ")));
+        }
+        num_written += write_if_changed(&self.dir.join("_index.md"), &index)? as usize;
+        Ok(num_written)
+    }
+}
+
+struct PageBuilder<'a> {
+    helper: &'a PrakriyaHelper,
+    dhatu_dir: PathBuf,
+    sup_dir: PathBuf,
+    steps: StepOptions,
+}
+
+impl PageBuilder<'_> {
+    /// The title of a dhAtu's pages and the name of their directory, which like `prakriyaa.py`
+    /// names them by the aupadeshika and gaNa. `code` tells apart roots listed more than once in
+    /// a gaNa.
+    fn dhatu_title_and_name(dhatu: &Dhatu, code: Option<&str>) -> (String, String) {
+        let aupadeshika = dhatu.aupadeshika().unwrap_or_default();
+        let mut title = dev(aupadeshika);
+        let mut text = aupadeshika.to_string();
+        if let Some(gana) = dhatu.gana() {
+            title.push_str(&format!(" ({})", dev(gana_name(gana))));
+            text.push_str(&format!(" {}", gana.as_str()));
+        }
+        if let Some(code) = code {
+            title.push_str(&format!(" {}", code));
+            text.push_str(&format!(" {}", code));
+        }
+        for sanadi in dhatu.sanadi() {
+            title.push_str(&format!(" + {}", dev(sanadi.as_str())));
+            text.push_str(&format!(" {}", sanadi.as_str()));
+        }
+        (title, storage_name(&text))
+    }
+
+    fn tinanta_pages(&self, dhatu: &Dhatu, code: Option<&str>) -> LemmaPages {
+        let (title, name) = Self::dhatu_title_and_name(dhatu, code);
+        let mut pages = LemmaPages::new(title, self.dhatu_dir.join("tiNantAni").join(name));
+        for prayoga in [Prayoga::Kartari, Prayoga::Karmani] {
+            for lakara in Lakara::iter() {
+                pages.start_group(format!(
+                    "{} {}",
+                    dev(lakara.to_string()),
+                    dev(prayoga.to_string())
+                ));
+                for dhatu_pada in [DhatuPada::Parasmaipada, DhatuPada::Atmanepada] {
+                    for purusha in Purusha::iter() {
+                        for vacana in Vacana::iter() {
+                            let Ok(tinanta) = Tinanta::builder()
+                                .dhatu(dhatu.clone())
+                                .prayoga(prayoga)
+                                .pada(dhatu_pada)
+                                .lakara(lakara)
+                                .purusha(purusha)
+                                .vacana(vacana)
+                                .build()
+                            else {
+                                continue;
+                            };
+                            let heading = [
+                                lakara.to_string(),
+                                prayoga.to_string(),
+                                dhatu_pada.to_string(),
+                                purusha.to_string(),
+                                vacana.to_string(),
+                            ]
+                            .map(dev)
+                            .join(" ");
+                            let prakriyas = self.helper.v.derive_tinantas(&tinanta);
+                            pages.add(self, &heading, &prakriyas);
+                        }
+                    }
+                }
+            }
+        }
+        pages
+    }
+
+    fn krdanta_pages(&self, dhatu: &Dhatu, code: Option<&str>) -> LemmaPages {
+        let (title, name) = Self::dhatu_title_and_name(dhatu, code);
+        let mut pages = LemmaPages::new(title, self.dhatu_dir.join("kRdantAni").join(name));
+        for krt in BaseKrt::iter() {
+            let heading = format!(
+                "{} + {}",
+                dev(dhatu.aupadeshika().unwrap_or_default()),
+                dev(krt.to_string())
+            );
+            pages.start_group(heading.clone());
+            let Ok(krdanta) = Krdanta::builder().dhatu(dhatu.clone()).krt(krt).build() else {
+                continue;
+            };
+            let prakriyas = self.helper.v.derive_krdantas(&krdanta);
+            pages.add(self, &heading, &prakriyas);
+        }
+        pages
+    }
+
+    fn subanta_pages(
+        &self,
+        pratipadika: &Pratipadika,
+        text: &str,
+        lingas: &[Linga],
+    ) -> LemmaPages {
+        let mut pages = LemmaPages::new(dev(text), self.sup_dir.join(storage_name(text)));
+        for linga in lingas {
+            pages.start_group(format!("{} {}", dev(text), dev(linga.to_string())));
+            for vibhakti in Vibhakti::iter() {
+                for vacana in Vacana::iter() {
+                    let Ok(subanta) = Subanta::builder()
+                        .pratipadika(pratipadika.clone())
+                        .linga(*linga)
+                        .vibhakti(vibhakti)
+                        .vacana(vacana)
+                        .build()
+                    else {
+                        continue;
+                    };
+                    let heading = [linga.to_string(), vibhakti.to_string(), vacana.to_string()]
+                        .map(dev)
+                        .join(" ");
+                    let prakriyas = self.helper.v.derive_subantas(&subanta);
+                    pages.add(self, &heading, &prakriyas);
+                }
+            }
+        }
+        pages
+    }
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    if args.dhatus.is_empty()
+        && args.pratipadikas.is_empty()
+        && !args.all_dhatus
+        && !args.all_pratipadikas
+    {
+        return Err("Give --dhatus, --pratipadikas, --all-dhatus or --all-pratipadikas".into());
+    }
+    let helper = PrakriyaHelper::new(&args.data.data_path);
+    let builder = PageBuilder {
+        helper: &helper,
+        dhatu_dir: args.dhatu_dir.clone(),
+        sup_dir: args.sup_dir.clone(),
+        steps: args.steps.clone(),
+    };
+
+    let wanted_dhatus: Vec<String> = args.dhatus.iter().map(to_slp).collect();
+    let root_and_gana = |dhatu: &Dhatu| {
+        (
+            dhatu.aupadeshika().map(str::to_string),
+            dhatu.gana().map(|x| x.as_str().to_string()),
+        )
+    };
+    let mut num_listings = HashMap::new();
+    for entry in helper.dhatupatha()? {
+        *num_listings.entry(root_and_gana(entry.dhatu())).or_insert(0) += 1;
+    }
+    // Each dhAtu, with its dhAtupATha number if that's needed to tell its pages apart.
+    let dhatus: Vec<(Dhatu, Option<String>)> = helper
+        .dhatupatha()?
+        .into_iter()
+        .filter(|entry| {
+            args.all_dhatus
+                || entry
+                    .dhatu()
+                    .aupadeshika()
+                    .is_some_and(|x| wanted_dhatus.iter().any(|w| w == x))
+        })
+        .map(|entry| {
+            let dhatu = entry.dhatu().clone();
+            let code = (num_listings[&root_and_gana(&dhatu)] > 1).then(|| entry.code().to_string());
+            (dhatu, code)
+        })
+        .collect();
+
+    let wanted_pratipadikas: Vec<String> = args.pratipadikas.iter().map(to_slp).collect();
+    // Only load the kosha if prAtipadika pages are asked for.
+    let pratipadikas: Vec<(Pratipadika, String, Vec<Linga>)> =
+        if args.all_pratipadikas || !wanted_pratipadikas.is_empty() {
+            helper
+                .kosha()?
+                .pratipadikas()
+                .filter_map(|entry| match entry {
+                    PratipadikaEntry::Basic(basic) => Some(basic),
+                    _ => None,
+                })
+                .filter(|basic| {
+                    args.all_pratipadikas
+                        || wanted_pratipadikas
+                            .iter()
+                            .any(|w| w == basic.pratipadika().text())
+                })
+                .map(|basic| {
+                    (
+                        Pratipadika::from(basic.pratipadika()),
+                        basic.pratipadika().text().to_string(),
+                        basic.lingas().to_vec(),
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+    for wanted in &wanted_dhatus {
+        if !dhatus.iter().any(|(d, _)| d.aupadeshika() == Some(wanted.as_str())) {
+            error!("No dhAtupATha dhAtu {}", dev(wanted));
+        }
+    }
+    for wanted in &wanted_pratipadikas {
+        if !pratipadikas.iter().any(|(_, text, _)| text == wanted) {
+            error!("No kosha prAtipadika {}", dev(wanted));
+        }
+    }
+
+    let progress_bar = ProgressBar::new((dhatus.len() + pratipadikas.len()) as u64);
+    let mut num_written = 0;
+    for (dhatu, code) in &dhatus {
+        num_written += builder.tinanta_pages(dhatu, code.as_deref()).write()?;
+        num_written += builder.krdanta_pages(dhatu, code.as_deref()).write()?;
+        progress_bar.inc(1);
+    }
+    for (pratipadika, text, lingas) in &pratipadikas {
+        num_written += builder.subanta_pages(pratipadika, text, lingas).write()?;
+        progress_bar.inc(1);
+    }
+    progress_bar.finish();
+    info!(
+        "Wrote {} changed pages for {} dhAtus and {} prAtipadikas.",
+        num_written,
+        dhatus.len(),
+        pratipadikas.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidyut_prakriya::args::{Gana, Slp1String};
+
+    #[test]
+    fn storage_names_follow_optitrans() {
+        assert_eq!(storage_name("rAma"), "rAma");
+        assert_eq!(storage_name("kfzRa"), "kRiShNa");
+        assert_eq!(storage_name("saYjaya"), "sanjaya");
+        assert_eq!(storage_name("gaNgA"), "gangA");
+    }
+
+    #[test]
+    fn storage_names_of_titles() {
+        assert_eq!(storage_name("BU BvAdi"), "bhU_bhvAdi");
+        assert_eq!(storage_name("BU / sattAyAm"), "bhU___sattAyAm");
+        assert_eq!(storage_name(&"rAma ".repeat(20)).len(), STORAGE_NAME_MAX_LEN);
+    }
+
+    #[test]
+    fn dhatu_names_include_gana_and_code() {
+        let dhatu = Dhatu::mula(Slp1String::from("BU").unwrap(), Gana::Bhvadi);
+        let (_, name) = PageBuilder::dhatu_title_and_name(&dhatu, None);
+        assert_eq!(name, storage_name("BU BvAdi"));
+        let (title, name) = PageBuilder::dhatu_title_and_name(&dhatu, Some("01.0001"));
+        assert!(title.ends_with(" 01.0001"));
+        assert_eq!(name, storage_name("BU BvAdi 01.0001"));
+    }
+}
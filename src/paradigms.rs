@@ -0,0 +1,225 @@
+//! Checks vidyut's tinantas and subantas against reference paradigms, reporting forms that are
+//! missing from vidyut's output and forms that vidyut adds.
+//!
+//! Reference paradigms are CSV files with one row per paradigm cell and the expected forms (SLP1)
+//! joined with `|`, as in the form lists:
+//!
+//! - tinantas: `code,sanadi,prayoga,lakara,pada,purusha,vacana,forms`, where `code` is a
+//!   dhAtupATha code and `pada` may be empty.
+//! - subantas: `pratipadika,linga,vibhakti,vacana,forms`.
+//!
+//! The references in `tests/paradigms` are checked by `cargo test` against the small dhAtupATha
+//! in `tests/data`, so no vidyut data build is needed.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- check-paradigms --tinantas my-tinantas.csv --subantas my-subantas.csv
+//!     cargo test paradigms
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use vidyut_prakriya::args::{Linga, Pratipadika, Slp1String, Subanta, Tinanta};
+use vidyut_prakriya::{Dhatupatha, Prakriya, Vyakarana};
+
+use crate::dicts::sanaadi::{SanaadiBase, SanaadiChain};
+use crate::util::parse_arg;
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Path to vidyut's `dhatupatha.tsv`, for the dhAtu codes in `--tinantas`.
+    #[arg(long, default_value = "data/dhatupatha.tsv")]
+    pub(crate) dhatupatha: PathBuf,
+
+    /// Reference tinanta paradigms.
+    #[arg(long)]
+    pub(crate) tinantas: Option<PathBuf>,
+
+    /// Reference subanta paradigms.
+    #[arg(long)]
+    pub(crate) subantas: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TinantaRow {
+    code: String,
+    sanadi: String,
+    prayoga: String,
+    lakara: String,
+    pada: String,
+    purusha: String,
+    vacana: String,
+    forms: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubantaRow {
+    pratipadika: String,
+    linga: String,
+    vibhakti: String,
+    vacana: String,
+    forms: String,
+}
+
+/// A paradigm cell where vidyut disagrees with the reference.
+#[derive(Debug)]
+pub(crate) struct Mismatch {
+    /// The cell, e.g. `01.0001 none Kartari Lat Parasmaipada Prathama Eka`.
+    pub(crate) cell: String,
+    /// Expected forms that vidyut doesn't derive.
+    pub(crate) missing: Vec<String>,
+    /// Forms that vidyut derives but the reference doesn't list.
+    pub(crate) extra: Vec<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: missing [{}], extra [{}]",
+            self.cell,
+            self.missing.join(", "),
+            self.extra.join(", ")
+        )
+    }
+}
+
+fn diff(cell: String, expected: &str, prakriyas: &[Prakriya]) -> Option<Mismatch> {
+    let expected: BTreeSet<String> = expected
+        .split('|')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect();
+    let actual: BTreeSet<String> = prakriyas.iter().map(|p| p.text()).collect();
+    if expected == actual {
+        return None;
+    }
+    Some(Mismatch {
+        cell,
+        missing: expected.difference(&actual).cloned().collect(),
+        extra: actual.difference(&expected).cloned().collect(),
+    })
+}
+
+pub(crate) fn check_tinantas(
+    v: &Vyakarana,
+    dhatupatha: &Dhatupatha,
+    path: &Path,
+) -> Result<Vec<Mismatch>, Box<dyn Error>> {
+    let mut mismatches = Vec::new();
+    for row in csv::Reader::from_path(path)?.deserialize() {
+        let row: TinantaRow = row?;
+        let entry = dhatupatha
+            .into_iter()
+            .find(|e| e.code() == row.code)
+            .ok_or_else(|| format!("No dhAtu {} in the dhAtupATha", row.code))?;
+        let chain: SanaadiChain = row.sanadi.parse()?;
+        chain.validate(SanaadiBase::Dhatu)?;
+
+        let mut builder = Tinanta::builder();
+        builder
+            .dhatu(entry.dhatu().clone().with_sanadi(chain.sanaadis()))
+            .prayoga(parse_arg(&row.prayoga)?)
+            .lakara(parse_arg(&row.lakara)?)
+            .purusha(parse_arg(&row.purusha)?)
+            .vacana(parse_arg(&row.vacana)?);
+        if !row.pada.is_empty() {
+            builder.pada(parse_arg(&row.pada)?);
+        }
+        let tinanta = builder.build()?;
+
+        let cell = [
+            &row.code,
+            &row.sanadi,
+            &row.prayoga,
+            &row.lakara,
+            &row.pada,
+            &row.purusha,
+            &row.vacana,
+        ]
+        .map(String::as_str)
+        .join(" ");
+        mismatches.extend(diff(cell, &row.forms, &v.derive_tinantas(&tinanta)));
+    }
+    Ok(mismatches)
+}
+
+pub(crate) fn check_subantas(v: &Vyakarana, path: &Path) -> Result<Vec<Mismatch>, Box<dyn Error>> {
+    let mut mismatches = Vec::new();
+    for row in csv::Reader::from_path(path)?.deserialize() {
+        let row: SubantaRow = row?;
+        let subanta = Subanta::builder()
+            .pratipadika(Pratipadika::basic(Slp1String::from(&row.pratipadika)?))
+            .linga(parse_arg::<Linga>(&row.linga)?)
+            .vibhakti(parse_arg(&row.vibhakti)?)
+            .vacana(parse_arg(&row.vacana)?)
+            .build()?;
+
+        let cell = [&row.pratipadika, &row.linga, &row.vibhakti, &row.vacana]
+            .map(String::as_str)
+            .join(" ");
+        mismatches.extend(diff(cell, &row.forms, &v.derive_subantas(&subanta)));
+    }
+    Ok(mismatches)
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let v = Vyakarana::builder().log_steps(false).build();
+    let mut mismatches = Vec::new();
+    if let Some(path) = &args.tinantas {
+        let dhatupatha = Dhatupatha::from_path(&args.dhatupatha)?;
+        mismatches.extend(check_tinantas(&v, &dhatupatha, path)?);
+    }
+    if let Some(path) = &args.subantas {
+        mismatches.extend(check_subantas(&v, path)?);
+    }
+
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
+    }
+    if !mismatches.is_empty() {
+        return Err(format!("{} paradigm cells differ from the reference", mismatches.len()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(path)
+    }
+
+    fn report(mismatches: &[Mismatch]) -> String {
+        let lines: Vec<_> = mismatches.iter().map(|m| m.to_string()).collect();
+        lines.join("\n")
+    }
+
+    #[test]
+    fn tinantas_match_reference() {
+        let v = Vyakarana::builder().log_steps(false).build();
+        let dhatupatha = Dhatupatha::from_path(fixture("data/dhatupatha.tsv")).unwrap();
+        let mismatches =
+            check_tinantas(&v, &dhatupatha, &fixture("paradigms/tinantas.csv")).unwrap();
+        assert!(mismatches.is_empty(), "{}", report(&mismatches));
+    }
+
+    #[test]
+    fn subantas_match_reference() {
+        let v = Vyakarana::builder().log_steps(false).build();
+        let mismatches = check_subantas(&v, &fixture("paradigms/subantas.csv")).unwrap();
+        assert!(mismatches.is_empty(), "{}", report(&mismatches));
+    }
+
+    #[test]
+    fn diff_reports_missing_and_extra_forms() {
+        let mismatch = diff("cell".to_string(), "a|b", &[]).unwrap();
+        assert_eq!(mismatch.missing, vec!["a", "b"]);
+        assert!(mismatch.extra.is_empty());
+        assert!(diff("cell".to_string(), "", &[]).is_none());
+    }
+}
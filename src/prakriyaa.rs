@@ -1,128 +1,308 @@
-use crate::util::{dev, slp};
+use crate::analysis::Analysis;
+use crate::dhaatu::{DhaatuInfo, Karmakatva};
+use crate::steps::{condense, Step, StepOptions};
+use crate::util::{dev, to_slp, to_slp_from};
 use log::{error, info};
-use std::path::Path;
-use std::sync::Arc;
-use vidyut_kosha::entries::{PadaEntry, SubantaEntry};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use vidyut_cheda::Chedaka;
+use vidyut_kosha::entries::PadaEntry;
 use vidyut_kosha::Kosha;
-use vidyut_prakriya::args::BaseKrt::kta;
-use vidyut_prakriya::args::{
-    Dhatu, DhatuPada, Gana, Krdanta, Krt, Lakara, Muladhatu, Pratipadika, Prayoga, Purusha, Sanadi,
-    Slp1String, Subanta, Tinanta, Vacana,
-};
-use vidyut_prakriya::{Dhatupatha, Prakriya, Vyakarana};
+use vidyut_prakriya::args::{Dhatu, Subanta, Tinanta};
+use vidyut_lipi::Scheme;
+use vidyut_prakriya::{Dhatupatha, Prakriya, Rule, Vyakarana};
 
+/// Where to find a vidyut data build.
+#[derive(clap::Args)]
+pub(crate) struct DataArgs {
+    /// Path to a vidyut data build, holding `kosha/` and `data/`.
+    #[arg(long, env = "VIDYUT_DATA", default_value = "vidyut-latest")]
+    pub(crate) data_path: PathBuf,
+}
+
+/// Derives padas and renders their prakriyAs. Data files are loaded on first use, so commands that
+/// only derive from specifications, or only need `dhatupatha.tsv`, work without a kosha.
 pub(crate) struct PrakriyaHelper {
     pub(crate) v: Arc<Vyakarana>,
-    pub(crate) kosha: Arc<Kosha>,
-    dhAtupATha: Dhatupatha,
+    kosha: OnceLock<Kosha>,
+    /// The segmenter, which holds a kosha of its own.
+    chedaka: OnceLock<Chedaka>,
+    dhAtupATha: OnceLock<Dhatupatha>,
+    /// sUtra texts (SLP1) keyed by code, e.g. `1.1.1`.
+    sUtrapATha: OnceLock<HashMap<String, String>>,
+    /// dhAtupATha (artha, code) pairs keyed by (aupadeshika, gaNa), in dhAtupATha order. A root
+    /// can be listed more than once in a gaNa, with different arthas.
+    dhAtu_codes: OnceLock<HashMap<(String, String), Vec<(String, String)>>>,
+    /// Optional karmakatva annotations keyed by dhAtupATha code.
+    karmakatvas: OnceLock<HashMap<String, Karmakatva>>,
+    /// Version of the vidyut data build, as recorded in its `VERSION` file.
+    pub(crate) data_version: String,
+    pub(crate) data_path: PathBuf,
 }
 
 impl PrakriyaHelper {
     pub(crate) fn new(data_path: &Path) -> Self {
-        let v = Arc::new(Vyakarana::new());
-        let kosha = Arc::new(Kosha::new(data_path.join("kosha/")).unwrap());
-        let dhAtupATha = match Dhatupatha::from_path(data_path.join("data/dhatupatha.tsv")) {
-            Ok(res) => res,
-            Err(err) => {
-                println!("{}", err);
-                std::process::exit(1);
+        Self {
+            v: Arc::new(Vyakarana::new()),
+            kosha: OnceLock::new(),
+            chedaka: OnceLock::new(),
+            dhAtupATha: OnceLock::new(),
+            sUtrapATha: OnceLock::new(),
+            dhAtu_codes: OnceLock::new(),
+            karmakatvas: OnceLock::new(),
+            data_version: Self::read_data_version(data_path),
+            data_path: data_path.to_path_buf(),
+        }
+    }
+
+    /// The kosha, loaded on first use. Fails if there's none in the data build, and tries again on
+    /// the next call.
+    ///
+    /// Once the chedaka is loaded, its kosha is used, so callers that segment text should load the
+    /// chedaka first to keep a single kosha in memory.
+    pub(crate) fn kosha(&self) -> Result<&Kosha, Box<dyn Error>> {
+        if let Some(chedaka) = self.chedaka.get() {
+            return Ok(chedaka.kosha());
+        }
+        if let Some(kosha) = self.kosha.get() {
+            return Ok(kosha);
+        }
+        let path = self.data_path.join("kosha/");
+        info!("Loading kosha from {}", path.display());
+        let kosha = Kosha::new(&path)
+            .map_err(|err| format!("Can't load kosha from {}: {}", path.display(), err))?;
+        Ok(self.kosha.get_or_init(|| kosha))
+    }
+
+    /// The vidyut-cheda segmenter, loaded on first use from the kosha, sandhi rules and model of
+    /// the data build.
+    pub(crate) fn chedaka(&self) -> Result<&Chedaka, Box<dyn Error>> {
+        if let Some(chedaka) = self.chedaka.get() {
+            return Ok(chedaka);
+        }
+        info!("Loading chedaka from {}", self.data_path.display());
+        let chedaka = Chedaka::new(&self.data_path)?;
+        Ok(self.chedaka.get_or_init(|| chedaka))
+    }
+
+    /// The dhAtupATha, loaded on first use. Fails if there's none in the data build.
+    pub(crate) fn dhatupatha(&self) -> Result<&Dhatupatha, Box<dyn Error>> {
+        if let Some(dhatupatha) = self.dhAtupATha.get() {
+            return Ok(dhatupatha);
+        }
+        let path = self.data_path.join("data/dhatupatha.tsv");
+        let dhatupatha = Dhatupatha::from_path(&path)
+            .map_err(|err| format!("Can't load {}: {}", path.display(), err))?;
+        Ok(self.dhAtupATha.get_or_init(|| dhatupatha))
+    }
+
+    fn sutrapatha(&self) -> &HashMap<String, String> {
+        self.sUtrapATha
+            .get_or_init(|| Self::read_sutras(&self.data_path.join("data/sutrapatha.tsv")))
+    }
+
+    /// Without a dhAtupATha there are no codes, which only means that entries are shown without
+    /// them.
+    fn dhatu_codes(&self) -> &HashMap<(String, String), Vec<(String, String)>> {
+        self.dhAtu_codes.get_or_init(|| {
+            let mut codes: HashMap<_, Vec<_>> = HashMap::new();
+            let dhatupatha = match self.dhatupatha() {
+                Ok(dhatupatha) => dhatupatha,
+                Err(err) => {
+                    error!("{}", err);
+                    return codes;
+                }
+            };
+            for entry in dhatupatha {
+                let dhatu = entry.dhatu();
+                let (Some(aupadeshika), Some(gana)) = (dhatu.aupadeshika(), dhatu.gana()) else {
+                    continue;
+                };
+                codes
+                    .entry((aupadeshika.to_string(), gana.as_str().to_string()))
+                    .or_default()
+                    .push((entry.artha().to_string(), entry.code().to_string()));
             }
-        };
-        let sUtrapATha = match Dhatupatha::from_path(data_path.join("data/sutrapatha.tsv")) {
-            Ok(res) => res,
+            codes
+        })
+    }
+
+    fn karmakatvas(&self) -> &HashMap<String, Karmakatva> {
+        self.karmakatvas
+            .get_or_init(|| Self::read_karmakatvas(&self.data_path.join("data/karmakatva.tsv")))
+    }
+
+    /// Falls back to the name of the data directory (e.g. `vidyut-latest`) if there's no
+    /// `VERSION` file.
+    fn read_data_version(data_path: &Path) -> String {
+        match fs::read_to_string(data_path.join("VERSION")) {
+            Ok(version) => version.trim().to_string(),
+            Err(_) => data_path
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+        }
+    }
+
+    /// Reads `code<TAB>text` lines, skipping a header line if present. A missing file only means
+    /// that steps are shown without sUtra texts.
+    fn read_sutras(path: &Path) -> HashMap<String, String> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
             Err(err) => {
-                println!("{}", err);
-                std::process::exit(1);
+                error!("Can't read {}: {}", path.display(), err);
+                return HashMap::new();
             }
         };
-        Self {
-            v,
-            kosha,
-            dhAtupATha,
-        }
+        content
+            .lines()
+            .filter_map(|line| {
+                let (code, text) = line.split_once('\t')?;
+                Some((code.trim().to_string(), text.trim().to_string()))
+            })
+            .filter(|(code, _)| code != "code")
+            .collect()
+    }
+
+    /// Reads `data/karmakatva.tsv`, which vidyut doesn't ship, since its dhAtupATha doesn't record
+    /// karmakatva. Each line is `code<TAB>karmakatva`, e.g. `01.0001<TAB>akarmaka`, where `code`
+    /// is a dhAtupATha number and `karmakatva` is `sakarmaka`, `akarmaka` or `dvikarmaka`,
+    /// optionally with a final `H`. Other lines, such as a header, are skipped, and a missing file
+    /// means no karmakatvas.
+    fn read_karmakatvas(path: &Path) -> HashMap<String, Karmakatva> {
+        let Ok(content) = fs::read_to_string(path) else {
+            return HashMap::new();
+        };
+        content
+            .lines()
+            .filter_map(|line| {
+                let (code, karmakatva) = line.split_once('\t')?;
+                Some((code.trim().to_string(), Karmakatva::from_slp(karmakatva)?))
+            })
+            .collect()
     }
 
-    fn show_prakriya(&self, prakriyas: Vec<Prakriya>) {
-        for p in prakriyas {
-            let mut steps = Vec::new();
-            for step in p.history() {
+    /// Describes `dhatu`. Its dhAtupATha number is looked up unless given as `code`. Where the root
+    /// is listed more than once in its gaNa, the entry with the same `artha` (in any script) is
+    /// used, or else the first.
+    pub(crate) fn dhaatu_info(&self, dhatu: &Dhatu, artha: &str, code: Option<&str>) -> DhaatuInfo {
+        let artha_slp = to_slp(artha);
+        let code = match (code, dhatu.aupadeshika(), dhatu.gana()) {
+            (Some(code), _, _) => Some(code.to_string()),
+            (None, Some(aupadeshika), Some(gana)) => self
+                .dhatu_codes()
+                .get(&(aupadeshika.to_string(), gana.as_str().to_string()))
+                .and_then(|codes| codes.iter().find(|(a, _)| *a == artha_slp).or(codes.first()))
+                .map(|(_, code)| code.clone()),
+            _ => None,
+        };
+        let karmakatva = code.as_ref().and_then(|c| self.karmakatvas().get(c).copied());
+        DhaatuInfo::new(dhatu, artha, code, karmakatva)
+    }
+
+    /// The steps of `p`, condensed as `options` asks.
+    pub(crate) fn steps(&self, p: &Prakriya, options: &StepOptions) -> Vec<Step> {
+        let steps = p
+            .history()
+            .iter()
+            .map(|step| {
                 // `step.result()` contains all of the *terms* that are part of this step. These
                 // include dhatus, agamas, pratyayas, etc.
-                //
-                // Here, we create a single string to show all of the results from each term.
                 let terms: Vec<_> = step
-                  .result()
-                  .iter()
-                  .map(|x| x.text())
-                  .filter(|x| !x.is_empty())
-                  .collect();
-
-                let url = format!(
-                    "[A](https://ashtadhyayi.github.io/suutra/{}/{}/)",
-                    &step.rule().code()[..3],
-                    step.rule().code()
-                );
-                let sutra_text = "???";
-                let detail = format!(
-                    "{} {} → {} {} {}",
-                    dev(&step.rule().code()), // TODO: get type
-                    step.rule().code(),
-                    dev(terms.join("/ ")),
-                    sutra_text,
-                    url
-                );
-                steps.push(detail);
-            }
+                    .result()
+                    .iter()
+                    .map(|x| x.text().to_string())
+                    .filter(|x| !x.is_empty())
+                    .collect();
+                let rule = step.rule();
+                let sutra = match rule {
+                    Rule::Ashtadhyayi(_) => self.sutrapatha().get(rule.code()).cloned(),
+                    _ => None,
+                };
+                Step::new(&rule, sutra, terms)
+            })
+            .collect();
+        condense(steps, options)
+    }
 
-            info!("## {}\n{}\n", dev(p.text()), steps.join("  \n"));
-        }
+    /// Renders each step as `<rule> → <terms> (<sUtra text> <link>)`, in Devanagari.
+    pub(crate) fn render_steps(&self, p: &Prakriya, options: &StepOptions) -> Vec<String> {
+        self.steps(p, options).iter().map(Step::to_markdown).collect()
     }
 
-    fn look_up_and_derive(&self, shabda: impl Into<String>) {
-        let shabda = shabda.into();
-        let entries = if shabda.chars().next().map_or(false, |c| c.is_ascii()) {
-            self.kosha.get_all(&shabda)
-        } else {
-            self.kosha.get_all(&slp(&shabda))
-        };
+    /// Renders a prakriyA as a Markdown section headed by its result, as on the vishvAsa pages.
+    pub(crate) fn render_prakriya(&self, p: &Prakriya, options: &StepOptions) -> String {
+        format!("## {}\n{}\n", dev(p.text()), self.render_steps(p, options).join("  \n"))
+    }
 
-        if entries.is_empty() {
-            error!("Can't get entry for {}", shabda);
-            return;
-        }
+    /// Like `render_prakriya`, but as an HTML `<section>`.
+    pub(crate) fn render_prakriya_html(&self, p: &Prakriya, options: &StepOptions) -> String {
+        let steps: Vec<_> = self.steps(p, options).iter().map(Step::to_html).collect();
+        format!(
+            "<section class=\"prakriya\">\n<h2>{}</h2>\n<ol>\n{}\n</ol>\n</section>\n",
+            dev(p.text()),
+            steps.join("\n")
+        )
+    }
 
-        for entry in entries {
-            let prakriyas = match entry {
-                PadaEntry::Subanta(s) => self.v.derive_subantas(
-                    &Subanta::builder()
-                        .pratipadika(s.pratipadika_entry())
-                        .vacana(s.vacana())
-                        .linga(s.linga())
-                        .vibhakti(s.vibhakti())
-                        .build()
-                        .unwrap(),
-                ),
-                _ => panic!("Expected BasicPratipadika"),
-            };
+    /// Derives the pada that a kosha entry describes. Entries other than subantas and tinantas
+    /// give nothing.
+    pub(crate) fn derive_entry(&self, entry: &PadaEntry) -> Vec<Prakriya> {
+        match entry {
+            PadaEntry::Subanta(s) => match Subanta::builder()
+                .pratipadika(s.pratipadika_entry())
+                .vacana(s.vacana())
+                .linga(s.linga())
+                .vibhakti(s.vibhakti())
+                .build()
+            {
+                Ok(subanta) => self.v.derive_subantas(&subanta),
+                Err(_) => vec![],
+            },
+            PadaEntry::Tinanta(t) => match Tinanta::builder()
+                .dhatu(t.dhatu_entry().dhatu().clone())
+                .prayoga(t.prayoga())
+                .lakara(t.lakara())
+                .purusha(t.purusha())
+                .vacana(t.vacana())
+                .pada(t.pada())
+                .build()
+            {
+                Ok(tinanta) => self.v.derive_tinantas(&tinanta),
+                Err(_) => vec![],
+            },
+            _ => vec![],
         }
     }
 
-    fn derive_and_print_prakriya(&self) {
-        let pada = Tinanta::builder()
-            .dhatu(Dhatu::mula(Slp1String::from("BU").unwrap(), Gana::Bhvadi))
-            .prayoga(Prayoga::Kartari)
-            .pada(DhatuPada::Parasmaipada)
-            .lakara(Lakara::Lat)
-            .purusha(Purusha::Prathama)
-            .vacana(Vacana::Eka)
-            .build();
-
-        // let spastaya = Dhatu::nama(Pratipadika::Basic(&slp("स्पष्ट")), Some(Sanadi::Ric));
-        // let pada = Krdanta::builder().dhatu(spastaya).krt(Krt::Base(kta)).build();
-
-        // match pada  {
-        //     Tinanta(t) => self.print_prakriya(t),
-        //     Krdanta(t) => self.print_prakriya(t),
-        // }
+    /// All kosha analyses of `form`, which is in `input_scheme` or else in whatever script is
+    /// detected. With `derive`, each analysis carries the steps of the prakriyA that produces
+    /// exactly this form, condensed as asked.
+    pub(crate) fn analyze(
+        &self,
+        form: &str,
+        input_scheme: Option<Scheme>,
+        scheme: Scheme,
+        derive: Option<&StepOptions>,
+    ) -> Result<Vec<Analysis>, Box<dyn Error>> {
+        let form = to_slp_from(form, input_scheme);
+        let analyses = self
+            .kosha()?
+            .get_all(&form)
+            .iter()
+            .map(|entry| {
+                let mut analysis = Analysis::new(&form, entry, scheme);
+                if let Some(options) = derive {
+                    if let Some(p) = self.derive_entry(entry).iter().find(|p| p.text() == form) {
+                        analysis.derivation = self.render_steps(p, options);
+                    }
+                }
+                analysis
+            })
+            .collect();
+        Ok(analyses)
     }
 }
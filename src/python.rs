@@ -0,0 +1,211 @@
+//! Python bindings, built as the `airaavata._core` extension module by `setup.py`, so that the
+//! curation scripts in `airaavata/` share the Rust implementation.
+//!
+//! Usage (from Python):
+//!
+//!     from airaavata._core import PrakriyaHelper, dump_subantas
+//!     helper = PrakriyaHelper("vidyut-latest")
+//!     helper.analyze("गच्छति", derive=True)
+//!     helper.derive("गच्छति")  # [("गच्छति", "## गच्छति\n..."), ...]
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use vidyut_lipi::Scheme;
+
+use crate::dicts::babylon::{BabylonDictionary, SanaadiDictFamily};
+use crate::dicts::sanaadi::{SanaadiBase, SanaadiChain};
+use crate::prakriyaa;
+use crate::steps::StepOptions;
+use crate::util::{dev, to_slp};
+
+fn parse_scheme(scheme: Option<&str>) -> PyResult<Option<Scheme>> {
+    scheme
+        .map(|s| {
+            s.parse()
+                .map_err(|_| PyValueError::new_err(format!("Unknown scheme {}", s)))
+        })
+        .transpose()
+}
+
+#[pyclass(name = "PrakriyaHelper", unsendable)]
+struct PyPrakriyaHelper(prakriyaa::PrakriyaHelper);
+
+#[pymethods]
+impl PyPrakriyaHelper {
+    /// Uses the kosha and data files of a vidyut data build, loading each on first use.
+    #[new]
+    fn new(data_path: PathBuf) -> Self {
+        Self(prakriyaa::PrakriyaHelper::new(&data_path))
+    }
+
+    #[getter]
+    fn data_version(&self) -> String {
+        self.0.data_version.clone()
+    }
+
+    /// All kosha analyses of `form`, as dicts with the fields of `lookup --json`. `form` is in
+    /// `input_scheme`, or else in whatever script is detected.
+    #[pyo3(signature = (form, scheme=None, derive=false, input_scheme=None))]
+    fn analyze<'py>(
+        &self,
+        py: Python<'py>,
+        form: &str,
+        scheme: Option<&str>,
+        derive: bool,
+        input_scheme: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let options = StepOptions::default();
+        let scheme = parse_scheme(scheme)?.unwrap_or(Scheme::Devanagari);
+        let analyses = self
+            .0
+            .analyze(form, parse_scheme(input_scheme)?, scheme, derive.then_some(&options))
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        pythonize::pythonize(py, &analyses).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    /// Rendered prakriyAs of every kosha analysis of `form` (in any script), as (derived form in
+    /// Devanagari, Markdown) pairs. Optional variants with the same text are each listed. The flags
+    /// are those of `StepOptions`.
+    #[pyo3(signature = (form, hide_unchanged=false, collapse_it=false, highlight=false))]
+    fn derive(
+        &self,
+        form: &str,
+        hide_unchanged: bool,
+        collapse_it: bool,
+        highlight: bool,
+    ) -> PyResult<Vec<(String, String)>> {
+        let options = StepOptions {
+            hide_unchanged,
+            collapse_it,
+            highlight,
+            ..Default::default()
+        };
+        let mut prakriyaas = Vec::new();
+        let kosha = self.0.kosha().map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        for entry in kosha.get_all(&to_slp(form)) {
+            for p in self.0.derive_entry(&entry) {
+                prakriyaas.push((dev(p.text()), self.0.render_prakriya(&p, &options)));
+            }
+        }
+        Ok(prakriyaas)
+    }
+}
+
+fn dictionary(data_path: &Path) -> BabylonDictionary {
+    BabylonDictionary::new(prakriyaa::PrakriyaHelper::new(data_path))
+}
+
+/// Runs a dictionary build with the GIL released, raising its error as a `RuntimeError`. The error
+/// is made a string before the GIL is taken back, since `Box<dyn Error>` isn't `Send`.
+fn build_without_gil(
+    py: Python<'_>,
+    build: impl FnOnce() -> Result<(), Box<dyn Error>> + Send,
+) -> PyResult<()> {
+    py.allow_threads(|| build().map_err(|e| e.to_string()))
+        .map_err(PyRuntimeError::new_err)
+}
+
+/// Writes the subanta Babylon dictionaries into `dest_dir`. `lemmas` is a TSV of prAtipadika,
+/// li~Nga and meaning to use instead of the kosha.
+#[pyfunction]
+#[pyo3(signature = (data_path, dest_dir, lemmas=None, overwrite=false))]
+fn dump_subantas(
+    py: Python<'_>,
+    data_path: PathBuf,
+    dest_dir: PathBuf,
+    lemmas: Option<PathBuf>,
+    overwrite: bool,
+) -> PyResult<()> {
+    build_without_gil(py, || {
+        dictionary(&data_path).dump_subantas(&dest_dir, lemmas.as_deref(), overwrite)
+    })
+}
+
+/// Writes the taddhitAnta Babylon dictionaries into `dest_dir`, with `lemmas` as in
+/// `dump_subantas`.
+#[pyfunction]
+#[pyo3(signature = (data_path, dest_dir, lemmas=None, overwrite=false))]
+fn dump_taddhitaantas(
+    py: Python<'_>,
+    data_path: PathBuf,
+    dest_dir: PathBuf,
+    lemmas: Option<PathBuf>,
+    overwrite: bool,
+) -> PyResult<()> {
+    build_without_gil(py, || {
+        dictionary(&data_path).dump_taddhitaantas(&dest_dir, lemmas.as_deref(), overwrite)
+    })
+}
+
+/// Writes sanAdi Babylon dictionaries of `family` (`krdanta` or `tinanta`) into `dest_dir`.
+/// `sanadi` is a list of chains like `none,Nic,san,Nic-san`; the default is the usual set.
+/// `lemmas` is a TSV of dhAtu, gaNa, dhAtupATha number and artha to use instead of the kosha.
+#[pyfunction]
+#[pyo3(signature = (data_path, dest_dir, family="krdanta", sanadi=None, lemmas=None, overwrite=false))]
+fn dump_sanaadi_dicts(
+    py: Python<'_>,
+    data_path: PathBuf,
+    dest_dir: PathBuf,
+    family: &str,
+    sanadi: Option<&str>,
+    lemmas: Option<PathBuf>,
+    overwrite: bool,
+) -> PyResult<()> {
+    let family = SanaadiDictFamily::from_name(family).map_err(PyValueError::new_err)?;
+    let chains = match sanadi {
+        Some(spec) => SanaadiChain::parse_list(spec, SanaadiBase::Dhatu).map_err(PyValueError::new_err)?,
+        None => SanaadiChain::defaults(),
+    };
+    build_without_gil(py, || {
+        dictionary(&data_path).dump_sanaadi_dicts(
+            &dest_dir,
+            &chains,
+            family,
+            lemmas.as_deref(),
+            overwrite,
+        )
+    })
+}
+
+/// Writes nAmadhAtu Babylon dictionaries of `family` into `dest_dir`. `pratipadikas` (in any
+/// script) limits the kosha prAtipadikas used; `sanadi` is a list of chains like `Nic,kyac,kyaN`,
+/// which is also the default.
+#[pyfunction]
+#[pyo3(signature = (data_path, dest_dir, pratipadikas=None, family="krdanta", sanadi=None, overwrite=false))]
+fn dump_naamadhaatu_dicts(
+    py: Python<'_>,
+    data_path: PathBuf,
+    dest_dir: PathBuf,
+    pratipadikas: Option<Vec<String>>,
+    family: &str,
+    sanadi: Option<&str>,
+    overwrite: bool,
+) -> PyResult<()> {
+    let family = SanaadiDictFamily::from_name(family).map_err(PyValueError::new_err)?;
+    let chains = match sanadi {
+        Some(spec) => {
+            SanaadiChain::parse_list(spec, SanaadiBase::Pratipadika).map_err(PyValueError::new_err)?
+        }
+        None => SanaadiChain::naama_defaults(),
+    };
+    build_without_gil(py, || {
+        dictionary(&data_path).dump_naamadhaatu_dicts(
+            &dest_dir,
+            pratipadikas.as_deref(),
+            &chains,
+            family,
+            overwrite,
+        )
+    })
+}
+
+#[pymodule]
+fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPrakriyaHelper>()?;
+    m.add_function(wrap_pyfunction!(dump_subantas, m)?)?;
+    m.add_function(wrap_pyfunction!(dump_taddhitaantas, m)?)?;
+    m.add_function(wrap_pyfunction!(dump_sanaadi_dicts, m)?)?;
+    m.add_function(wrap_pyfunction!(dump_naamadhaatu_dicts, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! An interactive shell for exploring derivations with `PrakriyaHelper`.
+//!
+//! Type a form (in any script) to see its analyses and prakriyAs, or `:pada` with a pada
+//! specification (see `pada.rs`) to derive it. The script of what's typed is detected unless set
+//! with `:input`, which is safer for ASCII. `:help` lists the other commands.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- repl
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::error::Error;
+use std::path::PathBuf;
+use vidyut_lipi::{transliterate, Mapping, Scheme};
+use vidyut_prakriya::Prakriya;
+
+use crate::pada::{parse_params, PadaSpec, Params};
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::steps::StepOptions;
+use crate::util::{parse_scheme, to_scheme};
+
+const HELP: &str = "\
+<form>               analyses of a form, in any script, with their prakriyAs
+:pada key=value ...  derive a pada, e.g. :pada dhatu=BU gana=Bhvadi lakara=Lat purusha=Prathama vacana=Eka
+                     or :pada pratipadika=rAma linga=Pum vibhakti=Prathama vacana=Eka
+:scheme <name>       display script, e.g. :scheme Iso15919
+:input <name>|auto   script of typed forms, e.g. :input Slp1, or auto to detect it
+:steps               toggle between prakriyAs and results only
+:condense            toggle hiding steps that don't change the text and collapsing it-saMjYA steps
+:help                this text
+:quit                exit (or Ctrl-D)";
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    /// History file. (Default: `~/.airaavata_history`)
+    #[arg(long)]
+    pub(crate) history: Option<PathBuf>,
+
+    /// Script of typed forms, such as `Slp1` or `HarvardKyoto`. (Default: detected, which can
+    /// misread ASCII text)
+    #[arg(long, value_parser = parse_scheme)]
+    pub(crate) input_scheme: Option<Scheme>,
+}
+
+struct Repl {
+    helper: PrakriyaHelper,
+    scheme: Scheme,
+    /// `None` to detect the script of each input.
+    input_scheme: Option<Scheme>,
+    show_steps: bool,
+    options: StepOptions,
+}
+
+impl Repl {
+    /// Steps are rendered in Devanagari, so they are transliterated from there.
+    fn print_steps(&self, steps: &[String]) {
+        if !self.show_steps {
+            return;
+        }
+        let mapping = Mapping::new(Scheme::Devanagari, self.scheme);
+        for step in steps {
+            println!("    {}", transliterate(step, &mapping));
+        }
+    }
+
+    fn print_prakriyas(&self, prakriyas: &[Prakriya]) {
+        if prakriyas.is_empty() {
+            println!("no derivation");
+        }
+        for p in prakriyas {
+            println!("{}", to_scheme(p.text(), self.scheme));
+            self.print_steps(&self.helper.render_steps(p, &self.options));
+        }
+    }
+
+    fn look_up(&self, form: &str) {
+        let derive = self.show_steps.then_some(&self.options);
+        let analyses = match self.helper.analyze(form, self.input_scheme, self.scheme, derive) {
+            Ok(analyses) => analyses,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        if analyses.is_empty() {
+            println!("{}: no analysis", form);
+        }
+        for analysis in &analyses {
+            println!("{}", analysis.summary());
+            self.print_steps(&analysis.derivation);
+        }
+    }
+
+    /// The `key=value` words of `text`, with the input script unless they name one.
+    fn pada_params(&self, text: &str) -> Params {
+        let mut params = parse_params(text);
+        if let Some(scheme) = self.input_scheme {
+            params
+                .entry("input_scheme".to_string())
+                .or_insert_with(|| format!("{:?}", scheme));
+        }
+        params
+    }
+
+    /// Handles one line. Returns false to exit.
+    fn eval(&mut self, line: &str) -> bool {
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            ":quit" | ":q" => return false,
+            ":help" => println!("{}", HELP),
+            ":steps" => {
+                self.show_steps = !self.show_steps;
+                println!("steps {}", if self.show_steps { "on" } else { "off" });
+            }
+            ":condense" => {
+                let condense = !self.options.hide_unchanged;
+                self.options.hide_unchanged = condense;
+                self.options.collapse_it = condense;
+                println!("condensed steps {}", if condense { "on" } else { "off" });
+            }
+            ":scheme" => match rest.trim().parse() {
+                Ok(scheme) => self.scheme = scheme,
+                Err(_) => println!("Unknown scheme {}", rest.trim()),
+            },
+            ":input" => match rest.trim() {
+                "auto" => self.input_scheme = None,
+                name => match parse_scheme(name) {
+                    Ok(scheme) => self.input_scheme = Some(scheme),
+                    Err(err) => println!("{}", err),
+                },
+            },
+            ":pada" => match PadaSpec::from_params(&self.pada_params(rest)) {
+                Ok(spec) => self.print_prakriyas(&spec.derive(&self.helper.v)),
+                Err(err) => println!("{}", err),
+            },
+            _ if command.starts_with(':') => println!("Unknown command {}; try :help", command),
+            _ => self.look_up(line),
+        }
+        true
+    }
+}
+
+fn default_history() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".airaavata_history"))
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut repl = Repl {
+        helper: PrakriyaHelper::new(&args.data.data_path),
+        scheme: Scheme::Devanagari,
+        input_scheme: args.input_scheme,
+        show_steps: true,
+        options: StepOptions::default(),
+    };
+    let history = args.history.or_else(default_history);
+
+    let mut editor = DefaultEditor::new()?;
+    if let Some(path) = &history {
+        // There's no history on the first run.
+        let _ = editor.load_history(path);
+    }
+    println!(
+        "airaavata {} ({}). :help for commands.",
+        env!("CARGO_PKG_VERSION"),
+        repl.helper.data_version
+    );
+
+    loop {
+        match editor.readline("> ") {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(line)?;
+                if !repl.eval(line) {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    if let Some(path) = &history {
+        editor.save_history(path)?;
+    }
+    Ok(())
+}
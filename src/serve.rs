@@ -0,0 +1,217 @@
+//! A localhost HTTP server answering with JSON, so that web tools can use one loaded
+//! `PrakriyaHelper` instead of calling the Python helpers in-process.
+//!
+//! Endpoints (all `GET`, parameters in the query string):
+//!
+//! - `/analyze?form=गच्छति&derive=true`: like `lookup --json`, with the script of `form` given as
+//!   `input_scheme` or else detected.
+//! - `/derive?dhatu=BU&gana=Bhvadi&lakara=Lat&purusha=Prathama&vacana=Eka`: the prakriyAs of a
+//!   pada, specified as in `pada.rs`.
+//! - `/paradigm?pratipadika=rAma&linga=Pum`: a paradigm table, specified likewise but without
+//!   purusha/vibhakti and vacana.
+//! - `/transliterate?text=rAmaH&to=Iso15919`: `from` is detected if not given.
+//!
+//! Each takes an optional `scheme` for its output (default `Devanagari`). `/analyze` and
+//! `/derive` also take the step options of `steps.rs` (`hide_unchanged`, `collapse_it`,
+//! `highlight` as `true`/`false`, and `sources` as a comma-separated list), and `/derive` takes
+//! `format=html` to get HTML instead of JSON.
+//!
+//! Requests are answered by `--jobs` threads sharing one `PrakriyaHelper`, so a slow derivation
+//! doesn't hold up the others.
+//!
+//! Usage:
+//!
+//!     cargo run --release -- serve --port 8642
+use clap::ValueEnum;
+use log::{error, info};
+use serde::Serialize;
+use std::error::Error;
+use std::thread;
+use std::time::Instant;
+use tiny_http::{Header, Request, Response, Server};
+use vidyut_lipi::{detect, transliterate, Mapping, Scheme};
+
+use crate::pada::{paradigm, PadaSpec, Params};
+use crate::prakriyaa::{DataArgs, PrakriyaHelper};
+use crate::steps::{RuleSource, Step, StepOptions};
+use crate::util::to_scheme;
+
+/// Command line arguments.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[command(flatten)]
+    pub(crate) data: DataArgs,
+
+    #[arg(long, default_value = "127.0.0.1")]
+    pub(crate) host: String,
+
+    #[arg(long, default_value_t = 8642)]
+    pub(crate) port: u16,
+
+    /// Number of requests to answer at once. 0 uses all cores.
+    #[arg(long, default_value_t = 4)]
+    pub(crate) jobs: usize,
+}
+
+#[derive(Serialize)]
+struct Derivation {
+    form: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Serialize)]
+struct Transliteration {
+    from: String,
+    to: String,
+    text: String,
+}
+
+/// A failed request: HTTP status and message.
+type Failure = (u16, String);
+
+const JSON: &str = "application/json; charset=utf-8";
+const HTML: &str = "text/html; charset=utf-8";
+
+/// A successful response: content type and body.
+type Reply = (&'static str, String);
+
+fn bad_request(message: impl Into<String>) -> Failure {
+    (400, message.into())
+}
+
+fn to_json(value: impl Serialize) -> Result<Reply, Failure> {
+    let body = serde_json::to_string(&value).map_err(|e| (500, e.to_string()))?;
+    Ok((JSON, body))
+}
+
+fn is_true(params: &Params, key: &str) -> bool {
+    params.get(key).is_some_and(|x| x == "true" || x == "1")
+}
+
+fn step_options(params: &Params) -> Result<StepOptions, Failure> {
+    let sources = match params.get("sources") {
+        Some(sources) => sources
+            .split(',')
+            .map(|x| RuleSource::from_str(x.trim(), true).map_err(bad_request))
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    Ok(StepOptions {
+        hide_unchanged: is_true(params, "hide_unchanged"),
+        collapse_it: is_true(params, "collapse_it"),
+        sources,
+        highlight: is_true(params, "highlight"),
+    })
+}
+
+fn parse_scheme(params: &Params, key: &str) -> Result<Option<Scheme>, Failure> {
+    params
+        .get(key)
+        .map(|s| s.parse().map_err(|_| bad_request(format!("Unknown scheme {}", s))))
+        .transpose()
+}
+
+fn handle(helper: &PrakriyaHelper, path: &str, params: &Params) -> Result<Reply, Failure> {
+    let scheme = parse_scheme(params, "scheme")?.unwrap_or(Scheme::Devanagari);
+    match path {
+        "/analyze" => {
+            let form = params.get("form").ok_or_else(|| bad_request("Missing form"))?;
+            let options = step_options(params)?;
+            let derive = is_true(params, "derive").then_some(&options);
+            let input_scheme = parse_scheme(params, "input_scheme")?;
+            to_json(
+                helper
+                    .analyze(form, input_scheme, scheme, derive)
+                    .map_err(|e| (500, e.to_string()))?,
+            )
+        }
+        "/derive" => {
+            let spec = PadaSpec::from_params(params).map_err(bad_request)?;
+            let options = step_options(params)?;
+            let prakriyas = spec.derive(&helper.v);
+            if params.get("format").is_some_and(|x| x == "html") {
+                let sections: Vec<_> = prakriyas
+                    .iter()
+                    .map(|p| helper.render_prakriya_html(p, &options))
+                    .collect();
+                return Ok((HTML, sections.join("\n")));
+            }
+            let derivations: Vec<_> = prakriyas
+                .iter()
+                .map(|p| Derivation {
+                    form: to_scheme(p.text(), scheme),
+                    steps: helper.steps(p, &options),
+                })
+                .collect();
+            to_json(derivations)
+        }
+        "/paradigm" => to_json(paradigm(&helper.v, params, scheme).map_err(bad_request)?),
+        "/transliterate" => {
+            let text = params.get("text").ok_or_else(|| bad_request("Missing text"))?;
+            let from = match parse_scheme(params, "from")? {
+                Some(from) => from,
+                None => detect(text).ok_or_else(|| bad_request("Can't detect the script"))?,
+            };
+            let to = parse_scheme(params, "to")?.unwrap_or(scheme);
+            to_json(Transliteration {
+                from: format!("{:?}", from),
+                to: format!("{:?}", to),
+                text: transliterate(text, &Mapping::new(from, to)),
+            })
+        }
+        _ => Err((404, format!("No endpoint {}", path))),
+    }
+}
+
+fn respond(helper: &PrakriyaHelper, request: Request) {
+    let start = Instant::now();
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let params: Params = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+
+    let (status, (content_type, body)) = match handle(helper, path, &params) {
+        Ok(reply) => (200, reply),
+        Err((status, message)) => (
+            status,
+            (JSON, serde_json::json!({ "error": message }).to_string()),
+        ),
+    };
+    let content_type = Header::from_bytes("Content-Type", content_type).expect("valid header");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(err) = request.respond(response) {
+        error!("Can't respond to {}: {}", url, err);
+    }
+    info!(
+        "{} {} {:.1}ms",
+        status,
+        url,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+}
+
+pub(crate) fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    // The kosha is loaded by the first `/analyze`, so that `/derive` and `/paradigm` work without
+    // one.
+    let helper = PrakriyaHelper::new(&args.data.data_path);
+    info!("Using vidyut data {}", helper.data_version);
+
+    let addr = format!("{}:{}", args.host, args.port);
+    let server = Server::http(&addr).map_err(|e| e.to_string())?;
+    let jobs = match args.jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    eprintln!("Listening on http://{} with {} threads", addr, jobs);
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    respond(&helper, request);
+                }
+            });
+        }
+    });
+    Ok(())
+}
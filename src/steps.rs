@@ -0,0 +1,387 @@
+//! Steps of a prakriyA as shown to readers, and options to condense them for teaching pages.
+//!
+//! The same filtered steps are rendered as Markdown (as on the vishvAsa pages), HTML or JSON.
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use vidyut_prakriya::Rule;
+
+use crate::util::dev;
+
+/// The text a rule comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RuleSource {
+    Ashtadhyayi,
+    Varttika,
+    Dhatupatha,
+    Kashika,
+    Kaumudi,
+    Linganushasana,
+    Phit,
+    Unadipatha,
+    Other,
+}
+
+impl RuleSource {
+    pub(crate) fn of(rule: &Rule) -> Self {
+        match rule {
+            Rule::Ashtadhyayi(_) => Self::Ashtadhyayi,
+            Rule::Varttika(_) => Self::Varttika,
+            Rule::Dhatupatha(_) => Self::Dhatupatha,
+            Rule::Kashika(_) => Self::Kashika,
+            Rule::Kaumudi(_) => Self::Kaumudi,
+            Rule::Linganushasana(_) => Self::Linganushasana,
+            Rule::Phit(_) => Self::Phit,
+            Rule::Unadipatha(_) => Self::Unadipatha,
+            _ => Self::Other,
+        }
+    }
+
+    /// Short Devanagari name, as on the vishvAsa prakriyA pages.
+    pub(crate) fn abbreviation(&self) -> &'static str {
+        match self {
+            Self::Ashtadhyayi => "अ",
+            Self::Varttika => "वा",
+            Self::Dhatupatha => "धा",
+            Self::Kashika => "का",
+            Self::Kaumudi => "कौ",
+            Self::Linganushasana => "लि",
+            Self::Phit => "फि",
+            Self::Unadipatha => "उ",
+            Self::Other => "?",
+        }
+    }
+}
+
+/// How to condense the steps of a prakriyA. The default shows every step as is.
+#[derive(clap::Args, Clone, Debug, Default)]
+pub(crate) struct StepOptions {
+    /// Hide steps that don't change the text, such as saMjYA assignments.
+    #[arg(long)]
+    pub(crate) hide_unchanged: bool,
+
+    /// Collapse consecutive it-saMjYA steps (1.3.2 to 1.3.9) into one.
+    #[arg(long)]
+    pub(crate) collapse_it: bool,
+
+    /// Show only steps whose rules come from these texts.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub(crate) sources: Vec<RuleSource>,
+
+    /// Mark the terms that each step changed.
+    #[arg(long)]
+    pub(crate) highlight: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct StepRule {
+    pub(crate) code: String,
+    /// sUtra text in SLP1, if we have it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sutra: Option<String>,
+}
+
+impl StepRule {
+    /// Link to the sUtra on ashtadhyayi.github.io, for aShTAdhyAyI rules.
+    fn url(&self, source: RuleSource) -> Option<String> {
+        (source == RuleSource::Ashtadhyayi).then(|| {
+            format!(
+                "https://ashtadhyayi.github.io/suutra/{}/{}/",
+                &self.code[..3.min(self.code.len())],
+                self.code
+            )
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Step {
+    pub(crate) source: RuleSource,
+    /// Usually one rule; more if it-saMjYA steps were collapsed.
+    pub(crate) rules: Vec<StepRule>,
+    /// Non-empty terms after this step, in SLP1.
+    pub(crate) terms: Vec<String>,
+    /// Indices of `terms` that this step inserted or changed, if highlighting was asked for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) changed: Vec<usize>,
+    /// Terms before this step.
+    #[serde(skip)]
+    before: Vec<String>,
+}
+
+impl Step {
+    pub(crate) fn new(rule: &Rule, sutra: Option<String>, terms: Vec<String>) -> Self {
+        Self {
+            source: RuleSource::of(rule),
+            rules: vec![StepRule {
+                code: rule.code().to_string(),
+                sutra,
+            }],
+            terms,
+            changed: Vec::new(),
+            before: Vec::new(),
+        }
+    }
+
+    /// Codes of the rules of this step, e.g. `1.3.3, 1.3.9`.
+    pub(crate) fn codes(&self) -> String {
+        let codes: Vec<_> = self.rules.iter().map(|r| r.code.as_str()).collect();
+        codes.join(", ")
+    }
+
+    fn is_it_samjna(&self) -> bool {
+        self.source == RuleSource::Ashtadhyayi
+            && self.rules.iter().all(|r| {
+                r.code
+                    .strip_prefix("1.3.")
+                    .and_then(|x| x.parse::<u32>().ok())
+                    .is_some_and(|x| (2..=9).contains(&x))
+            })
+    }
+
+    fn terms_marked(&self, mark: impl Fn(&str) -> String) -> String {
+        let terms: Vec<_> = self
+            .terms
+            .iter()
+            .enumerate()
+            .map(|(i, term)| {
+                if self.changed.contains(&i) {
+                    mark(&dev(term))
+                } else {
+                    dev(term)
+                }
+            })
+            .collect();
+        terms.join("/ ")
+    }
+
+    /// `<source> <code> → <terms> (<sUtra text> <link>)`, in Devanagari.
+    pub(crate) fn to_markdown(&self) -> String {
+        let glosses: Vec<_> = self
+            .rules
+            .iter()
+            .map(|r| {
+                let mut gloss = r.sutra.as_ref().map(dev).unwrap_or_else(|| "(??)".to_string());
+                if let Some(url) = r.url(self.source) {
+                    gloss.push_str(&format!(" [A]({})", url));
+                }
+                gloss
+            })
+            .collect();
+        format!(
+            "{} {} → {} ({})",
+            self.source.abbreviation(),
+            self.codes(),
+            self.terms_marked(|x| format!("**{}**", x)),
+            glosses.join("; ")
+        )
+    }
+
+    pub(crate) fn to_html(&self) -> String {
+        let rules: Vec<_> = self
+            .rules
+            .iter()
+            .map(|r| {
+                let code = match r.url(self.source) {
+                    Some(url) => format!("<a href=\"{}\">{}</a>", url, r.code),
+                    None => r.code.clone(),
+                };
+                let sutra = r.sutra.as_ref().map(dev).unwrap_or_else(|| "(??)".to_string());
+                format!("{} <span class=\"sutra\">{}</span>", code, escape_html(&sutra))
+            })
+            .collect();
+        format!(
+            "<li><span class=\"source\">{}</span> {} → <span class=\"terms\">{}</span></li>",
+            self.source.abbreviation(),
+            rules.join("; "),
+            self.terms_marked(|x| format!("<mark>{}</mark>", escape_html(x)))
+        )
+    }
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Applies `options` to the steps of a prakriyA, given in order.
+/// Indices of the terms of `after` outside a longest common subsequence with `before`, so that a
+/// term inserted before others doesn't make them all look changed.
+fn changed_terms(before: &[String], after: &[String]) -> Vec<usize> {
+    let (n, m) = (before.len(), after.len());
+    // lcs[i][j] is the length of the LCS of before[i..] and after[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changed = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while j < m {
+        if i < n && before[i] == after[j] {
+            i += 1;
+            j += 1;
+        } else if i < n && lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            changed.push(j);
+            j += 1;
+        }
+    }
+    changed
+}
+
+pub(crate) fn condense(steps: Vec<Step>, options: &StepOptions) -> Vec<Step> {
+    let mut condensed: Vec<Step> = Vec::new();
+    let mut before = Vec::new();
+    for mut step in steps {
+        step.before = std::mem::replace(&mut before, step.terms.clone());
+        if options.collapse_it && step.is_it_samjna() {
+            if let Some(last) = condensed.last_mut().filter(|x| x.is_it_samjna()) {
+                last.rules.append(&mut step.rules);
+                last.terms = step.terms;
+                continue;
+            }
+        }
+        condensed.push(step);
+    }
+
+    condensed
+        .into_iter()
+        .filter(|step| !options.hide_unchanged || step.terms != step.before)
+        .filter(|step| options.sources.is_empty() || options.sources.contains(&step.source))
+        .map(|mut step| {
+            if options.highlight {
+                step.changed = changed_terms(&step.before, &step.terms);
+            }
+            step
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(source: RuleSource, code: &str, terms: &[&str]) -> Step {
+        Step {
+            source,
+            rules: vec![StepRule {
+                code: code.to_string(),
+                sutra: None,
+            }],
+            terms: terms.iter().map(|x| x.to_string()).collect(),
+            changed: Vec::new(),
+            before: Vec::new(),
+        }
+    }
+
+    /// The first steps of the prakriyA of Bavati.
+    fn bhavati() -> Vec<Step> {
+        use RuleSource::{Ashtadhyayi as A, Dhatupatha as D};
+        vec![
+            step(D, "01.0001", &["BU"]),
+            step(A, "3.2.123", &["BU", "la~w"]),
+            step(A, "1.3.2", &["BU", "la~w"]),
+            step(A, "1.3.9", &["BU", "l"]),
+            step(A, "3.4.78", &["BU", "tip"]),
+            step(A, "1.3.3", &["BU", "tip"]),
+            step(A, "1.3.9", &["BU", "ti"]),
+            step(A, "3.1.68", &["BU", "Sap", "ti"]),
+        ]
+    }
+
+    fn codes(steps: &[Step]) -> Vec<String> {
+        steps.iter().map(Step::codes).collect()
+    }
+
+    #[test]
+    fn default_keeps_every_step() {
+        let steps = condense(bhavati(), &StepOptions::default());
+        assert_eq!(steps.len(), 8);
+        assert!(steps.iter().all(|x| x.changed.is_empty()));
+    }
+
+    #[test]
+    fn hide_unchanged() {
+        let options = StepOptions {
+            hide_unchanged: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            codes(&condense(bhavati(), &options)),
+            vec!["01.0001", "3.2.123", "1.3.9", "3.4.78", "1.3.9", "3.1.68"]
+        );
+    }
+
+    #[test]
+    fn collapse_it() {
+        let options = StepOptions {
+            collapse_it: true,
+            ..Default::default()
+        };
+        let steps = condense(bhavati(), &options);
+        assert_eq!(
+            codes(&steps),
+            vec!["01.0001", "3.2.123", "1.3.2, 1.3.9", "3.4.78", "1.3.3, 1.3.9", "3.1.68"]
+        );
+        assert_eq!(steps[2].terms, vec!["BU", "l"]);
+        assert_eq!(steps[4].terms, vec!["BU", "ti"]);
+    }
+
+    #[test]
+    fn collapsed_it_steps_count_as_changes() {
+        let options = StepOptions {
+            hide_unchanged: true,
+            collapse_it: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            codes(&condense(bhavati(), &options)),
+            vec!["01.0001", "3.2.123", "1.3.2, 1.3.9", "3.4.78", "1.3.3, 1.3.9", "3.1.68"]
+        );
+    }
+
+    #[test]
+    fn highlight_marks_changed_terms() {
+        let options = StepOptions {
+            highlight: true,
+            ..Default::default()
+        };
+        let steps = condense(bhavati(), &options);
+        assert_eq!(steps[0].changed, vec![0]);
+        assert_eq!(steps[2].changed, Vec::<usize>::new());
+        assert_eq!(steps[3].changed, vec![1]);
+        assert_eq!(steps[7].changed, vec![1]);
+        assert!(steps[7].to_markdown().contains("भू/ **शप्**/ ति"));
+        assert!(steps[7].to_html().contains("भू/ <mark>शप्</mark>/ ति"));
+    }
+
+    #[test]
+    fn changed_terms_are_aligned() {
+        let terms = |x: &[&str]| x.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(changed_terms(&terms(&["BU", "ti"]), &terms(&["BU", "Sap", "ti"])), vec![1]);
+        assert_eq!(
+            changed_terms(&terms(&["BU", "Sap", "ti"]), &terms(&["Bo", "a", "ti"])),
+            vec![0, 1]
+        );
+        assert!(changed_terms(&terms(&["Bav", "a", "ti"]), &terms(&["Bav", "ti"])).is_empty());
+    }
+
+    #[test]
+    fn sources() {
+        let options = StepOptions {
+            sources: vec![RuleSource::Dhatupatha],
+            ..Default::default()
+        };
+        assert_eq!(codes(&condense(bhavati(), &options)), vec!["01.0001"]);
+    }
+}
@@ -1,4 +1,6 @@
-use vidyut_lipi::{transliterate, Mapping, Scheme};
+use std::fmt::{Debug, Display};
+use vidyut_lipi::{detect, transliterate, Mapping, Scheme};
+use vidyut_prakriya::args::{DhatuPada, Gana, Lakara, Linga, Prayoga, Purusha, Vacana, Vibhakti};
 
 pub(crate) fn dev(x: impl AsRef<str>) -> String {
     transliterate(x.as_ref(), &Mapping::new(Scheme::Slp1, Scheme::Devanagari))
@@ -7,3 +9,87 @@ pub(crate) fn dev(x: impl AsRef<str>) -> String {
 pub(crate) fn slp(x: impl AsRef<str>) -> String {
     transliterate(x.as_ref(),  &Mapping::new(Scheme::Devanagari, Scheme::Slp1))
 }
+
+/// Transliterates from whatever script `x` is in to SLP1. Text whose script can't be detected is
+/// taken to be SLP1 already. ASCII text can often be read in more than one scheme (`Sakti` is SLP1
+/// for शक्ति but Harvard-Kyoto for षक्ति), so input options should let users name the scheme and
+/// pass it to `to_slp_from`.
+pub(crate) fn to_slp(x: impl AsRef<str>) -> String {
+    to_slp_from(x, None)
+}
+
+/// Like `to_slp`, but from `scheme` if given instead of the detected one.
+pub(crate) fn to_slp_from(x: impl AsRef<str>, scheme: Option<Scheme>) -> String {
+    match scheme.or_else(|| detect(x.as_ref())) {
+        Some(scheme) if scheme != Scheme::Slp1 => {
+            transliterate(x.as_ref(), &Mapping::new(scheme, Scheme::Slp1))
+        }
+        _ => x.as_ref().to_string(),
+    }
+}
+
+/// Parses a vidyut-lipi scheme name such as `Slp1` or `HarvardKyoto`, for use as a clap
+/// `value_parser`.
+pub(crate) fn parse_scheme(s: &str) -> Result<Scheme, String> {
+    s.parse().map_err(|_| format!("Unknown scheme {}", s))
+}
+
+pub(crate) fn to_scheme(x: impl AsRef<str>, scheme: Scheme) -> String {
+    if scheme == Scheme::Slp1 {
+        return x.as_ref().to_string();
+    }
+    transliterate(x.as_ref(), &Mapping::new(Scheme::Slp1, scheme))
+}
+
+/// vidyut's enums each have an inherent `iter()`; this lets `parse_arg` be generic over them.
+pub(crate) trait IterableEnum: Sized {
+    fn all() -> Vec<Self>;
+}
+
+macro_rules! iterable_enum {
+    ($($t:ty),*) => {
+        $(impl IterableEnum for $t {
+            fn all() -> Vec<Self> {
+                <$t>::iter().collect()
+            }
+        })*
+    };
+}
+
+iterable_enum!(DhatuPada, Gana, Lakara, Linga, Prayoga, Purusha, Vacana, Vibhakti);
+
+/// Parses a vidyut enum by its Rust name (`Bhvadi`) or its display name, for use as a clap
+/// `value_parser`.
+pub(crate) fn parse_arg<T: IterableEnum + Display + Debug>(s: &str) -> Result<T, String> {
+    T::all()
+        .into_iter()
+        .find(|x| x.to_string() == s || format!("{:?}", x).eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("Unknown value {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_scheme_is_detected() {
+        assert_eq!(to_slp("bhavati"), "Bavati");
+        assert_eq!(to_slp("kRRiShNa"), "kfzRa");
+        assert_eq!(to_slp("kfzRa"), "kfzRa");
+    }
+
+    #[test]
+    fn ascii_in_a_given_scheme() {
+        for word in ["BavAmi", "rAma", "gacCati", "kfzRa", "Sakti"] {
+            assert_eq!(to_slp_from(word, Some(Scheme::Slp1)), word);
+            assert_eq!(to_slp_from(dev(word), Some(Scheme::Devanagari)), word);
+        }
+        assert_eq!(to_slp_from("zakti", Some(Scheme::HarvardKyoto)), "Sakti");
+    }
+
+    #[test]
+    fn devanagari_to_slp1() {
+        assert_eq!(to_slp("भवामि"), "BavAmi");
+        assert_eq!(dev(to_slp("रामः")), "रामः");
+    }
+}